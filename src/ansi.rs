@@ -0,0 +1,292 @@
+use std::{iter::Peekable, str::FromStr};
+
+use crate::{
+	buffer::Cell,
+	style::{Colour, Style},
+};
+
+pub const ANSI_EXTENSION: &str = "ans";
+
+/// How many colours a terminal can show, which limits how exported colours are written
+#[derive(Clone, Copy, Debug, Default)]
+pub enum ColourDepth {
+	Basic,
+	#[default]
+	Indexed,
+	TrueColour,
+}
+
+impl FromStr for ColourDepth {
+	type Err = String;
+
+	fn from_str(s: &str) -> Result<Self, Self::Err> {
+		match s {
+			"16" => Ok(ColourDepth::Basic),
+			"256" => Ok(ColourDepth::Indexed),
+			"truecolour" | "truecolor" => Ok(ColourDepth::TrueColour),
+			other => Err(format!("unknown colour depth: {}", other)),
+		}
+	}
+}
+
+/// Writes rows of cells as text with SGR escape sequences, resetting the style at the end of each line
+pub fn export(rows: &[Vec<Cell>], depth: ColourDepth) -> String {
+	let mut output = String::new();
+	for row in rows {
+		let mut current = Style::default();
+		for cell in row {
+			if cell.style != current {
+				current = cell.style;
+				output.push_str(&sgr(current, depth));
+			}
+			output.push(cell.c);
+		}
+		if current != Style::default() {
+			output.push_str("\x1b[0m");
+		}
+		output.push('\n');
+	}
+	output
+}
+
+/// Reads text with SGR escape sequences into styled cells, skipping any other escape sequences
+pub fn import(text: &str) -> Vec<(isize, isize, Cell)> {
+	let mut cells = Vec::new();
+	let mut style = Style::default();
+	let (mut x, mut y) = (0, 0);
+
+	let mut chars = text.chars().peekable();
+	while let Some(c) = chars.next() {
+		match c {
+			'\x1b' => {
+				if let Some((parameters, 'm')) = control_sequence(&mut chars) {
+					apply_sgr(&parameters, &mut style);
+				}
+			}
+			'\n' => {
+				x = 0;
+				y += 1;
+			}
+			'\t' => x += 8 - x % 8,
+			c if c.is_control() => (),
+			c => {
+				let cell = Cell { c, style };
+				if !cell.is_blank() {
+					cells.push((x, y, cell));
+				}
+				x += 1;
+			}
+		}
+	}
+	cells
+}
+
+fn sgr(style: Style, depth: ColourDepth) -> String {
+	let mut parameters = vec![String::from("0")];
+	if style.bold {
+		parameters.push(String::from("1"));
+	}
+	if style.dim {
+		parameters.push(String::from("2"));
+	}
+	if let Some(colour) = style.foreground {
+		parameters.push(colour_parameters(colour, depth, 30));
+	}
+	if let Some(colour) = style.background {
+		parameters.push(colour_parameters(colour, depth, 40));
+	}
+	format!("\x1b[{}m", parameters.join(";"))
+}
+
+/// The parameters selecting a colour, where `base` is 30 for the foreground or 40 for the background
+fn colour_parameters(colour: Colour, depth: ColourDepth, base: u8) -> String {
+	let index = match (depth, colour) {
+		(ColourDepth::TrueColour, Colour::Rgb(r, g, b)) => {
+			return format!("{};2;{};{};{}", base + 8, r, g, b)
+		}
+		(ColourDepth::Basic, colour) => colour
+			.index()
+			.filter(|index| *index < 16)
+			.unwrap_or_else(|| nearest(colour.rgb(), 0..16)),
+		(_, colour) => colour
+			.index()
+			.unwrap_or_else(|| nearest(colour.rgb(), 16..=255)),
+	};
+
+	match index {
+		0..=7 => (base + index).to_string(),
+		8..=15 => (base + 60 + index - 8).to_string(),
+		index => format!("{};5;{}", base + 8, index),
+	}
+}
+
+/// The palette index with the closest colour
+fn nearest((r, g, b): (u8, u8, u8), indices: impl Iterator<Item = u8>) -> u8 {
+	let distance = |index: u8| {
+		let (pr, pg, pb) = Colour::from_index(index).rgb();
+		let square = |a: u8, b: u8| (a as i32 - b as i32).pow(2);
+		square(r, pr) + square(g, pg) + square(b, pb)
+	};
+	indices.min_by_key(|index| distance(*index)).unwrap_or(0)
+}
+
+/// Reads the rest of an escape sequence, returning its parameters and final character if it is a
+/// control sequence
+fn control_sequence(chars: &mut Peekable<impl Iterator<Item = char>>) -> Option<(String, char)> {
+	if chars.next_if_eq(&'[').is_none() {
+		// Other escape sequences are a single character long
+		chars.next();
+		return None;
+	}
+
+	let mut parameters = String::new();
+	for c in chars {
+		if ('\x40'..='\x7e').contains(&c) {
+			return Some((parameters, c));
+		}
+		parameters.push(c);
+	}
+	None
+}
+
+fn apply_sgr(parameters: &str, style: &mut Style) {
+	let mut codes = parameters
+		.split(';')
+		.map(|code| code.parse::<u8>().unwrap_or(0));
+
+	while let Some(code) = codes.next() {
+		match code {
+			0 => *style = Style::default(),
+			1 => style.bold = true,
+			2 => style.dim = true,
+			22 => {
+				style.bold = false;
+				style.dim = false;
+			}
+			30..=37 => style.foreground = Some(Colour::from_index(code - 30)),
+			38 => style.foreground = extended_colour(&mut codes),
+			39 => style.foreground = None,
+			40..=47 => style.background = Some(Colour::from_index(code - 40)),
+			48 => style.background = extended_colour(&mut codes),
+			49 => style.background = None,
+			90..=97 => style.foreground = Some(Colour::from_index(code - 90 + 8)),
+			100..=107 => style.background = Some(Colour::from_index(code - 100 + 8)),
+			_ => (),
+		}
+	}
+}
+
+/// Reads the rest of a 256 colour or truecolour selection
+fn extended_colour(codes: &mut impl Iterator<Item = u8>) -> Option<Colour> {
+	match codes.next()? {
+		5 => codes.next().map(Colour::from_index),
+		2 => Some(Colour::Rgb(codes.next()?, codes.next()?, codes.next()?)),
+		_ => None,
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn cell(c: char, style: Style) -> Cell { Cell { c, style } }
+
+	fn imported(text: &str) -> Vec<(isize, isize, char, Style)> {
+		import(text)
+			.into_iter()
+			.map(|(x, y, cell)| (x, y, cell.c, cell.style))
+			.collect()
+	}
+
+	#[test]
+	fn exports_style_changes_and_resets_at_line_end() {
+		let red = Style {
+			foreground: Some(Colour::Red),
+			..Style::default()
+		};
+		let rows = vec![
+			vec![cell('a', Style::default()), cell('b', red), cell('c', red)],
+			vec![cell('d', Style::default())],
+		];
+		assert_eq!(
+			export(&rows, ColourDepth::Indexed),
+			"a\x1b[0;31mbc\x1b[0m\nd\n"
+		);
+	}
+
+	#[test]
+	fn exports_colours_at_each_depth() {
+		let colour = Colour::Rgb(255, 0, 0);
+		assert_eq!(
+			colour_parameters(colour, ColourDepth::TrueColour, 30),
+			"38;2;255;0;0"
+		);
+		assert_eq!(
+			colour_parameters(colour, ColourDepth::Indexed, 30),
+			"38;5;196"
+		);
+		assert_eq!(colour_parameters(colour, ColourDepth::Basic, 40), "101");
+		assert_eq!(
+			colour_parameters(Colour::Indexed(123), ColourDepth::Indexed, 40),
+			"48;5;123"
+		);
+	}
+
+	#[test]
+	fn imports_styled_cells_and_skips_blanks() {
+		let bold = Style {
+			bold: true,
+			..Style::default()
+		};
+		assert_eq!(
+			imported("a \x1b[1mb\x1b[0m\n\tc"),
+			vec![
+				(0, 0, 'a', Style::default()),
+				(2, 0, 'b', bold),
+				(8, 1, 'c', Style::default()),
+			]
+		);
+	}
+
+	#[test]
+	fn imports_coloured_blanks_and_skips_other_sequences() {
+		let background = Style {
+			background: Some(Colour::Blue),
+			..Style::default()
+		};
+		assert_eq!(
+			imported("\x1b[2J\x1bM\x1b[44m \x1b[49m "),
+			vec![(0, 0, ' ', background)]
+		);
+	}
+
+	#[test]
+	fn applies_sgr_parameters() {
+		let mut style = Style::default();
+		apply_sgr("1;2;93;48;5;200", &mut style);
+		assert_eq!(
+			style,
+			Style {
+				foreground: Some(Colour::BrightYellow),
+				background: Some(Colour::Indexed(200)),
+				bold: true,
+				dim: true,
+			}
+		);
+
+		apply_sgr("22;38;2;1;2;3;39", &mut style);
+		assert_eq!(
+			style,
+			Style {
+				background: Some(Colour::Indexed(200)),
+				..Style::default()
+			}
+		);
+
+		apply_sgr("38;2;1;2;3", &mut style);
+		assert_eq!(style.foreground, Some(Colour::Rgb(1, 2, 3)));
+
+		apply_sgr("", &mut style);
+		assert_eq!(style, Style::default());
+	}
+}
@@ -0,0 +1,271 @@
+use crossterm::{
+	cursor::MoveTo,
+	queue,
+	style::{Attribute, Print, SetAttribute, SetBackgroundColor, SetForegroundColor},
+	terminal::{Clear, ClearType},
+};
+
+use std::io::Write;
+
+use crate::{clipboard::SetClipboard, error::Result, style::Style};
+
+/// A render target for elements, either a real terminal or an in-memory screen
+pub trait Backend {
+	fn move_to(&mut self, x: u16, y: u16) -> Result<()>;
+
+	fn print(&mut self, text: &str) -> Result<()>;
+
+	fn set_reverse(&mut self, reverse: bool) -> Result<()>;
+
+	fn set_style(&mut self, style: Style) -> Result<()>;
+
+	fn clear(&mut self) -> Result<()>;
+
+	fn set_clipboard(&mut self, text: &str) -> Result<()>;
+
+	fn flush(&mut self) -> Result<()>;
+}
+
+/// Renders to a terminal, keeping the previous frame so that only changed cells are sent
+pub struct TerminalBackend<W: Write> {
+	w: W,
+	screen: GridBackend,
+	previous: Option<GridBackend>,
+}
+
+impl<W: Write> TerminalBackend<W> {
+	pub fn new(w: W, width: u16, height: u16) -> Self {
+		Self {
+			w,
+			screen: GridBackend::new(width, height),
+			previous: None,
+		}
+	}
+
+	pub fn resize(&mut self, width: u16, height: u16) {
+		self.screen.resize(width, height);
+		self.previous = None;
+	}
+
+	fn queue_run(
+		&mut self,
+		x: usize,
+		y: usize,
+		cells: &[GridCell],
+		attributes: &mut (bool, Style),
+	) -> Result<()> {
+		queue!(self.w, MoveTo(x as u16, y as u16))?;
+		for cell in cells {
+			if (cell.reverse, cell.style) != *attributes {
+				*attributes = (cell.reverse, cell.style);
+				self.queue_attributes(cell.reverse, cell.style)?;
+			}
+			queue!(self.w, Print(cell.c))?;
+		}
+		Ok(())
+	}
+
+	/// Resets the terminal's attributes and then applies the given ones
+	fn queue_attributes(&mut self, reverse: bool, style: Style) -> Result<()> {
+		queue!(self.w, SetAttribute(Attribute::Reset))?;
+		if reverse {
+			queue!(self.w, SetAttribute(Attribute::Reverse))?;
+		}
+		if style.bold {
+			queue!(self.w, SetAttribute(Attribute::Bold))?;
+		}
+		if style.dim {
+			queue!(self.w, SetAttribute(Attribute::Dim))?;
+		}
+		if let Some(colour) = style.foreground {
+			queue!(self.w, SetForegroundColor(colour.into()))?;
+		}
+		if let Some(colour) = style.background {
+			queue!(self.w, SetBackgroundColor(colour.into()))?;
+		}
+		Ok(())
+	}
+}
+
+impl<W: Write> Backend for TerminalBackend<W> {
+	fn move_to(&mut self, x: u16, y: u16) -> Result<()> { self.screen.move_to(x, y) }
+
+	fn print(&mut self, text: &str) -> Result<()> { self.screen.print(text) }
+
+	fn set_reverse(&mut self, reverse: bool) -> Result<()> { self.screen.set_reverse(reverse) }
+
+	fn set_style(&mut self, style: Style) -> Result<()> { self.screen.set_style(style) }
+
+	fn clear(&mut self) -> Result<()> {
+		queue!(self.w, Clear(ClearType::All))?;
+		self.screen.clear()?;
+		self.previous = None;
+		Ok(())
+	}
+
+	fn set_clipboard(&mut self, text: &str) -> Result<()> {
+		queue!(self.w, SetClipboard(text.to_string()))?;
+		Ok(())
+	}
+
+	fn flush(&mut self) -> Result<()> {
+		let screen = self.screen.clone();
+		let previous = self.previous.take();
+		let mut attributes = (false, Style::default());
+		queue!(self.w, SetAttribute(Attribute::Reset))?;
+
+		for (y, row) in screen.cells.iter().enumerate() {
+			let previous_row = previous.as_ref().and_then(|previous| previous.cells.get(y));
+			let changed = |x: usize| match previous_row.and_then(|previous_row| previous_row.get(x))
+			{
+				Some(previous_cell) => *previous_cell != row[x],
+				None => true,
+			};
+
+			// Send each run of changed cells after a single cursor movement
+			let mut x = 0;
+			while x < row.len() {
+				if !changed(x) {
+					x += 1;
+					continue;
+				}
+				let start = x;
+				while x < row.len() && changed(x) {
+					x += 1;
+				}
+				self.queue_run(start, y, &row[start..x], &mut attributes)?;
+			}
+		}
+
+		if attributes != (false, Style::default()) {
+			queue!(self.w, SetAttribute(Attribute::Reset))?;
+		}
+		self.previous = Some(screen);
+		self.w.flush()?;
+		Ok(())
+	}
+}
+
+#[derive(Clone, Copy, PartialEq)]
+struct GridCell {
+	c: char,
+	reverse: bool,
+	style: Style,
+}
+
+impl Default for GridCell {
+	fn default() -> Self {
+		Self {
+			c: ' ',
+			reverse: false,
+			style: Style::default(),
+		}
+	}
+}
+
+/// An in-memory screen, used to drive the editor without a terminal
+#[derive(Clone)]
+pub struct GridBackend {
+	width: u16,
+	height: u16,
+	cells: Vec<Vec<GridCell>>,
+	cursor: (u16, u16),
+	reverse: bool,
+	style: Style,
+	clipboard: Option<String>,
+}
+
+impl GridBackend {
+	pub fn new(width: u16, height: u16) -> Self {
+		Self {
+			width,
+			height,
+			cells: vec![vec![GridCell::default(); width as usize]; height as usize],
+			cursor: (0, 0),
+			reverse: false,
+			style: Style::default(),
+			clipboard: None,
+		}
+	}
+
+	pub fn resize(&mut self, width: u16, height: u16) { *self = Self::new(width, height); }
+
+	pub fn size(&self) -> (u16, u16) { (self.width, self.height) }
+
+	/// The characters on screen, one string per row
+	pub fn lines(&self) -> Vec<String> {
+		self.cells
+			.iter()
+			.map(|row| row.iter().map(|cell| cell.c).collect::<String>())
+			.collect::<Vec<_>>()
+	}
+
+	pub fn is_reversed(&self, x: u16, y: u16) -> bool {
+		self.cells
+			.get(y as usize)
+			.and_then(|row| row.get(x as usize))
+			.map(|cell| cell.reverse)
+			.unwrap_or(false)
+	}
+
+	pub fn style(&self, x: u16, y: u16) -> Style {
+		self.cells
+			.get(y as usize)
+			.and_then(|row| row.get(x as usize))
+			.map(|cell| cell.style)
+			.unwrap_or_default()
+	}
+
+	/// The text most recently sent to the system clipboard
+	pub fn clipboard(&self) -> Option<&str> { self.clipboard.as_deref() }
+}
+
+impl Backend for GridBackend {
+	fn move_to(&mut self, x: u16, y: u16) -> Result<()> {
+		self.cursor = (x, y);
+		Ok(())
+	}
+
+	fn print(&mut self, text: &str) -> Result<()> {
+		for c in text.chars() {
+			let (x, y) = self.cursor;
+			if let Some(cell) = self
+				.cells
+				.get_mut(y as usize)
+				.and_then(|row| row.get_mut(x as usize))
+			{
+				*cell = GridCell {
+					c,
+					reverse: self.reverse,
+					style: self.style,
+				};
+			}
+			self.cursor = (x.saturating_add(1), y);
+		}
+		Ok(())
+	}
+
+	fn set_reverse(&mut self, reverse: bool) -> Result<()> {
+		self.reverse = reverse;
+		Ok(())
+	}
+
+	fn set_style(&mut self, style: Style) -> Result<()> {
+		self.style = style;
+		Ok(())
+	}
+
+	fn clear(&mut self) -> Result<()> {
+		self.cells
+			.iter_mut()
+			.for_each(|row| row.iter_mut().for_each(|cell| *cell = GridCell::default()));
+		Ok(())
+	}
+
+	fn set_clipboard(&mut self, text: &str) -> Result<()> {
+		self.clipboard = Some(text.to_string());
+		Ok(())
+	}
+
+	fn flush(&mut self) -> Result<()> { Ok(()) }
+}
@@ -1,5 +1,7 @@
 use bitflags::bitflags;
 
+use serde::{Deserialize, Serialize};
+
 bitflags! {
 	pub struct BoxFlags: u8 {
 		const NONE = 0b0000;
@@ -11,31 +13,82 @@ bitflags! {
 }
 
 impl BoxFlags {
-	pub fn from_char(c: char, ascii_mode: bool) -> Self {
-		match ascii_mode {
-			false => match c {
-				'╋' => Self::UP | Self::DOWN | Self::LEFT | Self::RIGHT,
+	/// Infers the directions an ascii box drawing character connects in from its neighbours
+	pub fn from_ascii_neighbours(c: char, up: char, down: char, left: char, right: char) -> Self {
+		let vertical = |c: char| c == '|' || c == '+';
+		let horizontal = |c: char| c == '-' || c == '+';
+		match c {
+			'+' => {
+				let mut flags = Self::NONE;
+				flags.set(Self::UP, vertical(up));
+				flags.set(Self::DOWN, vertical(down));
+				flags.set(Self::LEFT, horizontal(left));
+				flags.set(Self::RIGHT, horizontal(right));
+				flags
+			}
+			// Lone dashes and bars are more likely to be text than lines
+			'-' if horizontal(left) || horizontal(right) => Self::LEFT | Self::RIGHT,
+			'|' if vertical(up) || vertical(down) => Self::UP | Self::DOWN,
+			_ => Self::NONE,
+		}
+	}
 
-				'┳' => Self::DOWN | Self::LEFT | Self::RIGHT,
-				'┻' => Self::UP | Self::LEFT | Self::RIGHT,
-				'┣' => Self::UP | Self::DOWN | Self::RIGHT,
-				'┫' => Self::UP | Self::DOWN | Self::LEFT,
+	/// Converts a unicode drawing character to its ascii equivalent
+	pub fn ascii_glyph(c: char) -> char {
+		match c {
+			'█' => return '#',
+			'╱' => return '/',
+			'╲' => return '\\',
+			'╳' => return 'X',
+			_ => (),
+		}
+		let flags = Self::from_char(c, false);
+		if flags == Self::NONE {
+			c
+		}
+		else {
+			flags.to_char(true)
+		}
+	}
 
-				'┛' => Self::UP | Self::LEFT,
-				'┗' => Self::UP | Self::RIGHT,
-				'┓' => Self::DOWN | Self::LEFT,
-				'┏' => Self::DOWN | Self::RIGHT,
+	/// Adds lines in these directions to a character already on the canvas, joining them up with
+	/// any lines it has
+	pub fn draw(self, existing: char, style: LineStyle, ascii_mode: bool) -> char {
+		if ascii_mode {
+			return (self | Self::from_char(existing, true)).to_char(true);
+		}
 
-				'┃' => Self::UP | Self::DOWN,
-				'━' => Self::LEFT | Self::RIGHT,
+		let mut arms = arms(existing).unwrap_or([Weight::None; 4]);
+		for (arm, direction) in arms.iter_mut().zip(DIRECTIONS) {
+			if self.contains(direction) {
+				*arm = style.weight();
+			}
+		}
+		glyph(arms, style)
+	}
 
-				'╹' => Self::UP,
-				'╻' => Self::DOWN,
-				'╸' => Self::LEFT,
-				'╺' => Self::RIGHT,
+	fn from_arms(arms: Arms) -> Self {
+		DIRECTIONS
+			.iter()
+			.zip(arms)
+			.filter(|(_, arm)| *arm != Weight::None)
+			.fold(Self::NONE, |flags, (direction, _)| flags | *direction)
+	}
 
-				_ => Self::NONE,
-			},
+	fn arms(self, weight: Weight) -> Arms {
+		DIRECTIONS.map(|direction| {
+			if self.contains(direction) {
+				weight
+			}
+			else {
+				Weight::None
+			}
+		})
+	}
+
+	pub fn from_char(c: char, ascii_mode: bool) -> Self {
+		match ascii_mode {
+			false => arms(c).map(Self::from_arms).unwrap_or(Self::NONE),
 			true => match c {
 				'+' => Self::UP | Self::DOWN | Self::LEFT | Self::RIGHT,
 				'|' => Self::UP | Self::DOWN,
@@ -47,41 +100,13 @@ impl BoxFlags {
 
 	pub fn to_char(self, ascii_mode: bool) -> char {
 		match ascii_mode {
-			false => match (
-				self.contains(Self::UP),
-				self.contains(Self::DOWN),
-				self.contains(Self::LEFT),
-				self.contains(Self::RIGHT),
-			) {
-				(true, true, true, true) => '╋',
-
-				(false, true, true, true) => '┳',
-				(true, false, true, true) => '┻',
-				(true, true, false, true) => '┣',
-				(true, true, true, false) => '┫',
-
-				(true, false, true, false) => '┛',
-				(true, false, false, true) => '┗',
-				(false, true, true, false) => '┓',
-				(false, true, false, true) => '┏',
-
-				(true, true, false, false) => '┃',
-				(false, false, true, true) => '━',
-
-				(true, false, false, false) => '╹',
-				(false, true, false, false) => '╻',
-				(false, false, true, false) => '╸',
-				(false, false, false, true) => '╺',
-
-				_ => ' ',
-			},
+			false => glyph(self.arms(Weight::Heavy), LineStyle::Heavy),
 			true => match (
 				self.contains(Self::UP),
 				self.contains(Self::DOWN),
 				self.contains(Self::LEFT),
 				self.contains(Self::RIGHT),
 			) {
-				(true, true, true, false) => ' ',
 				(true, true, false, false) => '|',
 				(false, false, true, true) => '-',
 
@@ -95,3 +120,235 @@ impl BoxFlags {
 		}
 	}
 }
+
+/// The style of lines drawn by the rectangle and line tools
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LineStyle {
+	#[default]
+	Heavy,
+	Light,
+	Double,
+	Rounded,
+	Dashed,
+}
+
+impl LineStyle {
+	pub fn next(self) -> Self {
+		match self {
+			Self::Heavy => Self::Light,
+			Self::Light => Self::Double,
+			Self::Double => Self::Rounded,
+			Self::Rounded => Self::Dashed,
+			Self::Dashed => Self::Heavy,
+		}
+	}
+
+	pub fn name(self) -> &'static str {
+		match self {
+			Self::Heavy => "heavy",
+			Self::Light => "light",
+			Self::Double => "double",
+			Self::Rounded => "rounded",
+			Self::Dashed => "dashed",
+		}
+	}
+
+	fn weight(self) -> Weight {
+		match self {
+			Self::Heavy => Weight::Heavy,
+			Self::Double => Weight::Double,
+			Self::Light | Self::Rounded | Self::Dashed => Weight::Light,
+		}
+	}
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Weight {
+	None,
+	Light,
+	Heavy,
+	Double,
+}
+
+impl Weight {
+	/// How far apart two weights look, where drawing an extra arm is better than leaving one out
+	fn distance(self, other: Self) -> u32 {
+		match (self, other) {
+			(a, b) if a == b => 0,
+			(Weight::None, _) => 3,
+			(_, Weight::None) => 5,
+			(Weight::Double, _) | (_, Weight::Double) => 4,
+			_ => 1,
+		}
+	}
+}
+
+/// The weight of each arm of a box drawing character, in the same order as `DIRECTIONS`
+type Arms = [Weight; 4];
+
+const DIRECTIONS: [BoxFlags; 4] = [
+	BoxFlags::UP,
+	BoxFlags::DOWN,
+	BoxFlags::LEFT,
+	BoxFlags::RIGHT,
+];
+
+const N: Weight = Weight::None;
+const L: Weight = Weight::Light;
+const H: Weight = Weight::Heavy;
+const D: Weight = Weight::Double;
+
+/// Every solid box drawing character, covering all mixes of light and heavy arms and the mixes of
+/// single and double arms that unicode has
+#[rustfmt::skip]
+const SOLID: [(char, Arms); 109] = [
+	('─', [N, N, L, L]), ('━', [N, N, H, H]), ('│', [L, L, N, N]), ('┃', [H, H, N, N]),
+	('┌', [N, L, N, L]), ('┍', [N, L, N, H]), ('┎', [N, H, N, L]), ('┏', [N, H, N, H]),
+	('┐', [N, L, L, N]), ('┑', [N, L, H, N]), ('┒', [N, H, L, N]), ('┓', [N, H, H, N]),
+	('└', [L, N, N, L]), ('┕', [L, N, N, H]), ('┖', [H, N, N, L]), ('┗', [H, N, N, H]),
+	('┘', [L, N, L, N]), ('┙', [L, N, H, N]), ('┚', [H, N, L, N]), ('┛', [H, N, H, N]),
+	('├', [L, L, N, L]), ('┝', [L, L, N, H]), ('┞', [H, L, N, L]), ('┟', [L, H, N, L]),
+	('┠', [H, H, N, L]), ('┡', [H, L, N, H]), ('┢', [L, H, N, H]), ('┣', [H, H, N, H]),
+	('┤', [L, L, L, N]), ('┥', [L, L, H, N]), ('┦', [H, L, L, N]), ('┧', [L, H, L, N]),
+	('┨', [H, H, L, N]), ('┩', [H, L, H, N]), ('┪', [L, H, H, N]), ('┫', [H, H, H, N]),
+	('┬', [N, L, L, L]), ('┭', [N, L, H, L]), ('┮', [N, L, L, H]), ('┯', [N, L, H, H]),
+	('┰', [N, H, L, L]), ('┱', [N, H, H, L]), ('┲', [N, H, L, H]), ('┳', [N, H, H, H]),
+	('┴', [L, N, L, L]), ('┵', [L, N, H, L]), ('┶', [L, N, L, H]), ('┷', [L, N, H, H]),
+	('┸', [H, N, L, L]), ('┹', [H, N, H, L]), ('┺', [H, N, L, H]), ('┻', [H, N, H, H]),
+	('┼', [L, L, L, L]), ('┽', [L, L, H, L]), ('┾', [L, L, L, H]), ('┿', [L, L, H, H]),
+	('╀', [H, L, L, L]), ('╁', [L, H, L, L]), ('╂', [H, H, L, L]), ('╃', [H, L, H, L]),
+	('╄', [H, L, L, H]), ('╅', [L, H, H, L]), ('╆', [L, H, L, H]), ('╇', [H, L, H, H]),
+	('╈', [L, H, H, H]), ('╉', [H, H, H, L]), ('╊', [H, H, L, H]), ('╋', [H, H, H, H]),
+	('═', [N, N, D, D]), ('║', [D, D, N, N]),
+	('╒', [N, L, N, D]), ('╓', [N, D, N, L]), ('╔', [N, D, N, D]),
+	('╕', [N, L, D, N]), ('╖', [N, D, L, N]), ('╗', [N, D, D, N]),
+	('╘', [L, N, N, D]), ('╙', [D, N, N, L]), ('╚', [D, N, N, D]),
+	('╛', [L, N, D, N]), ('╜', [D, N, L, N]), ('╝', [D, N, D, N]),
+	('╞', [L, L, N, D]), ('╟', [D, D, N, L]), ('╠', [D, D, N, D]),
+	('╡', [L, L, D, N]), ('╢', [D, D, L, N]), ('╣', [D, D, D, N]),
+	('╤', [N, L, D, D]), ('╥', [N, D, L, L]), ('╦', [N, D, D, D]),
+	('╧', [L, N, D, D]), ('╨', [D, N, L, L]), ('╩', [D, N, D, D]),
+	('╪', [L, L, D, D]), ('╫', [D, D, L, L]), ('╬', [D, D, D, D]),
+	('╴', [N, N, L, N]), ('╵', [L, N, N, N]), ('╶', [N, N, N, L]), ('╷', [N, L, N, N]),
+	('╸', [N, N, H, N]), ('╹', [H, N, N, N]), ('╺', [N, N, N, H]), ('╻', [N, H, N, N]),
+	('╼', [N, N, L, H]), ('╽', [L, H, N, N]), ('╾', [N, N, H, L]), ('╿', [H, L, N, N]),
+];
+
+#[rustfmt::skip]
+const ROUNDED: [(char, Arms); 4] = [
+	('╭', [N, L, N, L]), ('╮', [N, L, L, N]), ('╯', [L, N, L, N]), ('╰', [L, N, N, L]),
+];
+
+/// Dashed lines, where the first of each direction is the one drawn
+#[rustfmt::skip]
+const DASHED: [(char, Arms); 12] = [
+	('┄', [N, N, L, L]), ('┆', [L, L, N, N]), ('┅', [N, N, H, H]), ('┇', [H, H, N, N]),
+	('┈', [N, N, L, L]), ('┊', [L, L, N, N]), ('┉', [N, N, H, H]), ('┋', [H, H, N, N]),
+	('╌', [N, N, L, L]), ('╎', [L, L, N, N]), ('╍', [N, N, H, H]), ('╏', [H, H, N, N]),
+];
+
+/// How a box drawing character looks, for exports which draw its lines rather than its glyph
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct Shape {
+	arms: Arms,
+	pub rounded: bool,
+	pub dashed: bool,
+}
+
+impl Shape {
+	pub fn of(c: char) -> Option<Self> {
+		Some(Self {
+			arms: arms(c)?,
+			rounded: ROUNDED.iter().any(|(glyph, _)| *glyph == c),
+			dashed: DASHED.iter().any(|(glyph, _)| *glyph == c),
+		})
+	}
+
+	/// The weight of the arm going in a direction
+	pub fn arm(self, direction: BoxFlags) -> Weight {
+		DIRECTIONS
+			.iter()
+			.position(|d| *d == direction)
+			.map(|index| self.arms[index])
+			.unwrap_or(Weight::None)
+	}
+
+	/// How far the line on one side of a double arm stops short of the centre, in gaps between the
+	/// two lines, so that the lines of double corners and junctions meet rather than cross
+	pub fn inset(self, side: BoxFlags) -> isize {
+		let opposite = match side {
+			BoxFlags::UP => BoxFlags::DOWN,
+			BoxFlags::DOWN => BoxFlags::UP,
+			BoxFlags::LEFT => BoxFlags::RIGHT,
+			_ => BoxFlags::LEFT,
+		};
+		match (self.arm(side), self.arm(opposite)) {
+			(Weight::Double, _) => 1,
+			(Weight::None, Weight::Double) => -1,
+			_ => 0,
+		}
+	}
+}
+
+fn arms(c: char) -> Option<Arms> {
+	SOLID
+		.iter()
+		.chain(ROUNDED.iter())
+		.chain(DASHED.iter())
+		.find(|(glyph, _)| *glyph == c)
+		.map(|(_, arms)| *arms)
+}
+
+/// The character for a set of arms, in a style's own glyphs where it has one, or otherwise the closest
+/// solid glyph
+fn glyph(arms: Arms, style: LineStyle) -> char {
+	if arms == [Weight::None; 4] {
+		return ' ';
+	}
+
+	let styled = match style {
+		LineStyle::Rounded => &ROUNDED[..],
+		LineStyle::Dashed => &DASHED[..],
+		_ => &[],
+	};
+	let distance = |candidate: &Arms| {
+		arms.iter()
+			.zip(candidate)
+			.map(|(wanted, have)| wanted.distance(*have))
+			.sum::<u32>()
+	};
+	styled
+		.iter()
+		.find(|(_, candidate)| *candidate == arms)
+		.or_else(|| {
+			SOLID
+				.iter()
+				.min_by_key(|(_, candidate)| distance(candidate))
+		})
+		.map(|(glyph, _)| *glyph)
+		.unwrap_or(' ')
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn ascii_joins_use_plus() {
+		for flags in [
+			BoxFlags::UP | BoxFlags::DOWN | BoxFlags::LEFT,
+			BoxFlags::UP | BoxFlags::DOWN | BoxFlags::RIGHT,
+			BoxFlags::UP | BoxFlags::LEFT | BoxFlags::RIGHT,
+			BoxFlags::DOWN | BoxFlags::LEFT | BoxFlags::RIGHT,
+			BoxFlags::UP | BoxFlags::DOWN | BoxFlags::LEFT | BoxFlags::RIGHT,
+			BoxFlags::DOWN | BoxFlags::RIGHT,
+		]
+		.iter()
+		{
+			assert_eq!(flags.to_char(true), '+');
+		}
+		assert_eq!((BoxFlags::UP | BoxFlags::DOWN).to_char(true), '|');
+		assert_eq!((BoxFlags::LEFT | BoxFlags::RIGHT).to_char(true), '-');
+	}
+}
@@ -1,9 +1,51 @@
-use std::iter::once;
+use std::collections::HashMap;
 
+use crate::style::Style;
+
+/// The width and height of each tile of cells
+const TILE_SIZE: isize = 16;
+
+type Tile = [[Cell; TILE_SIZE as usize]; TILE_SIZE as usize];
+
+#[derive(Clone, Copy, PartialEq)]
+pub struct Cell {
+	pub c: char,
+	pub style: Style,
+}
+
+impl Cell {
+	/// Whether the cell shows nothing, which a space with a background colour still does
+	pub fn is_blank(&self) -> bool { self.c == ' ' && self.style.background.is_none() }
+}
+
+/// Splits a row of cells into runs which share a style
+pub fn style_runs(row: &[Cell]) -> Vec<&[Cell]> {
+	let mut runs = Vec::new();
+	let mut start = 0;
+	for end in 1..=row.len() {
+		if end == row.len() || row[end].style != row[start].style {
+			runs.push(&row[start..end]);
+			start = end;
+		}
+	}
+	runs
+}
+
+impl Default for Cell {
+	fn default() -> Self {
+		Self {
+			c: ' ',
+			style: Style::default(),
+		}
+	}
+}
+
+/// A sparse canvas of cells, stored as fixed size tiles so that memory only scales with what is drawn
+#[derive(Clone)]
 pub struct Buffer {
-	start_x: usize,
-	start_y: usize,
-	grid: Vec<Vec<char>>,
+	start_x: isize,
+	start_y: isize,
+	tiles: HashMap<(isize, isize), Tile>,
 }
 
 impl Buffer {
@@ -11,54 +53,133 @@ impl Buffer {
 		Self {
 			start_x: 0,
 			start_y: 0,
-			grid: Vec::new(),
+			tiles: HashMap::new(),
 		}
 	}
 
 	pub fn new_frame(&mut self) {
 		self.start_x = 0;
 		self.start_y = 0;
-		self.grid.clear();
+		self.tiles.clear();
 	}
 
-	pub fn new_frame_bounded(&mut self, start_x: usize, start_y: usize) {
+	pub fn new_frame_bounded(&mut self, start_x: isize, start_y: isize) {
 		self.start_x = start_x;
 		self.start_y = start_y;
-		self.grid.clear();
+		self.tiles.clear();
 	}
 
-	pub fn get_point(&self, x: usize, y: usize) -> char {
-		let x = x - self.start_x;
-		let y = y - self.start_y;
-		*self.grid.get(y).and_then(|y| y.get(x)).unwrap_or(&' ')
+	pub fn get_point(&self, x: isize, y: isize) -> char { self.get_cell(x, y).c }
+
+	pub fn get_cell(&self, x: isize, y: isize) -> Cell {
+		let (tile, (x, y)) = Self::locate(x, y);
+		self.tiles
+			.get(&tile)
+			.map(|tile| tile[y][x])
+			.unwrap_or_default()
 	}
 
-	pub fn render_point(&mut self, x: usize, y: usize, c: char) {
-		let x = x - self.start_x;
-		let y = y - self.start_y;
+	pub fn render_point(&mut self, x: isize, y: isize, c: char, style: Style) {
+		let (tile, (x, y)) = Self::locate(x, y);
+		self.tiles
+			.entry(tile)
+			.or_insert([[Cell::default(); TILE_SIZE as usize]; TILE_SIZE as usize])[y][x] =
+			Cell { c, style };
+	}
 
-		if self.grid.len() < y + 1 {
-			self.grid.resize(y + 1, Vec::new());
+	/// Copies the drawn points of another buffer that fall within the given bounds
+	pub fn copy_bounded(
+		&mut self,
+		other: &Buffer,
+		min_x: isize,
+		max_x: isize,
+		min_y: isize,
+		max_y: isize,
+	) {
+		for y in min_y..max_y {
+			for x in min_x..max_x {
+				let cell = other.get_cell(x, y);
+				if !cell.is_blank() {
+					self.render_point(x, y, cell.c, cell.style);
+				}
+			}
 		}
-		let row = &mut self.grid[y];
-		if row.len() < x + 1 {
-			row.resize(x + 1, ' ');
-		}
-		row[x] = c;
 	}
 
-	pub fn into_inner(self) -> Vec<Vec<char>> { self.grid }
+	/// The drawn lines, trimmed to the bounding box of everything that isn't blank
+	pub fn trimmed_lines(&self) -> Vec<String> {
+		self.trimmed_cells()
+			.into_iter()
+			.map(|row| {
+				row.into_iter()
+					.map(|cell| cell.c)
+					.collect::<String>()
+					.trim_end()
+					.to_string()
+			})
+			.collect::<Vec<_>>()
+	}
 
-	pub fn output(&self, width: usize) -> Vec<String> {
-		self.grid
+	/// The drawn cells, trimmed to the bounding box of everything that isn't blank
+	pub fn trimmed_cells(&self) -> Vec<Vec<Cell>> {
+		let bounds = self
+			.tiles
 			.iter()
-			.map(|line| {
-				line.iter()
-					.copied()
-					.chain(once(' ').cycle())
-					.take(width)
-					.collect::<String>()
+			.flat_map(|((tile_x, tile_y), tile)| {
+				tile.iter().enumerate().flat_map(move |(y, row)| {
+					row.iter()
+						.enumerate()
+						.filter(|(_, cell)| !cell.is_blank())
+						.map(move |(x, _)| {
+							(
+								tile_x * TILE_SIZE + x as isize,
+								tile_y * TILE_SIZE + y as isize,
+							)
+						})
+				})
+			})
+			.fold(None, |acc, (x, y)| match acc {
+				Some((min_x, max_x, min_y, max_y)) => {
+					Some((x.min(min_x), x.max(max_x), y.min(min_y), y.max(max_y)))
+				}
+				None => Some((x, x, y, y)),
+			});
+
+		match bounds {
+			Some((min_x, max_x, min_y, max_y)) => (min_y..=max_y)
+				.map(|y| {
+					let mut row = (min_x..=max_x)
+						.map(|x| self.get_cell(x, y))
+						.collect::<Vec<_>>();
+					while row.last().map(Cell::is_blank).unwrap_or(false) {
+						row.pop();
+					}
+					row
+				})
+				.collect::<Vec<_>>(),
+			None => Vec::new(),
+		}
+	}
+
+	/// The rows of cells in the frame, starting from where it is bounded
+	pub fn output(&self, width: usize, height: usize) -> Vec<Vec<Cell>> {
+		(self.start_y..self.start_y + height as isize)
+			.map(|y| {
+				(self.start_x..self.start_x + width as isize)
+					.map(|x| self.get_cell(x, y))
+					.collect::<Vec<_>>()
 			})
 			.collect::<Vec<_>>()
 	}
+
+	/// Splits a point into the tile containing it and its position within that tile
+	fn locate(x: isize, y: isize) -> ((isize, isize), (usize, usize)) {
+		(
+			(x.div_euclid(TILE_SIZE), y.div_euclid(TILE_SIZE)),
+			(
+				x.rem_euclid(TILE_SIZE) as usize,
+				y.rem_euclid(TILE_SIZE) as usize,
+			),
+		)
+	}
 }
@@ -0,0 +1,56 @@
+use crate::{
+	buffer::Buffer,
+	tools::{merge_bounding_boxes, Tool},
+};
+
+/// Finished tools composited into one buffer, so that only the tool being drawn has to be rendered
+/// each frame
+pub struct Canvas {
+	buffer: Buffer,
+	ascii_mode: bool,
+	baked: usize,
+	bounding_box: Option<(isize, isize, isize, isize)>,
+}
+
+impl Canvas {
+	pub fn new() -> Self {
+		let mut buffer = Buffer::new();
+		buffer.new_frame();
+		Self {
+			buffer,
+			ascii_mode: false,
+			baked: 0,
+			bounding_box: None,
+		}
+	}
+
+	/// Discards the composited tools, which must be done whenever a finished tool is changed or
+	/// removed
+	pub fn invalidate(&mut self) {
+		self.buffer.new_frame();
+		self.baked = 0;
+		self.bounding_box = None;
+	}
+
+	/// Bakes every tool except the last, which is still being drawn
+	pub fn update(&mut self, tools: &[Box<dyn Tool>], ascii_mode: bool) {
+		let finished = tools.len().saturating_sub(1);
+		if ascii_mode != self.ascii_mode || finished < self.baked {
+			self.invalidate();
+			self.ascii_mode = ascii_mode;
+		}
+
+		for tool in &tools[self.baked..finished] {
+			tool.render(&mut self.buffer, ascii_mode);
+			self.bounding_box = merge_bounding_boxes(self.bounding_box, tool.bounding_box());
+		}
+		self.baked = finished;
+	}
+
+	/// The number of tools from the start of the history that have been baked
+	pub fn baked(&self) -> usize { self.baked }
+
+	pub fn bounding_box(&self) -> Option<(isize, isize, isize, isize)> { self.bounding_box }
+
+	pub fn buffer(&self) -> &Buffer { &self.buffer }
+}
@@ -0,0 +1,111 @@
+use structopt::StructOpt;
+
+use std::{path::PathBuf, str::FromStr};
+
+use crate::{ansi::ColourDepth, comment::LineRange, markdown::BlockSelector, png::CellSize};
+
+#[derive(StructOpt)]
+#[structopt(about = "Terminal based unicode and ascii diagrams and drawings")]
+pub struct Options {
+	#[structopt(subcommand)]
+	pub command: Option<Command>,
+}
+
+#[derive(StructOpt)]
+pub enum Command {
+	/// Open a drawing in the editor
+	Edit(EditOptions),
+	/// Print a drawing as plain or styled text, or as an image
+	Render {
+		/// Document or plain text file to render
+		file: PathBuf,
+		#[structopt(flatten)]
+		options: RenderOptions,
+	},
+	/// Convert a plain text drawing between unicode and ascii
+	Convert {
+		/// Plain text file to convert
+		file: PathBuf,
+		/// Character set to convert to
+		#[structopt(long, possible_values = &["ascii", "unicode"])]
+		to: Charset,
+	},
+	/// `tpaint <file> [options]` is shorthand for `tpaint edit <file> [options]`
+	#[structopt(external_subcommand)]
+	File(Vec<String>),
+}
+
+#[derive(Default, StructOpt)]
+pub struct EditOptions {
+	/// Document or plain text file to edit
+	pub file: Option<PathBuf>,
+	/// Edit only a fenced code block of a markdown file, picked by its number counting from 1 or by
+	/// a word in its info string
+	#[structopt(long, requires = "file")]
+	pub block: Option<BlockSelector>,
+	/// Edit only a range of commented lines of a source file, given as <start>-<end> counting from 1
+	#[structopt(long, requires = "file", conflicts_with = "block")]
+	pub lines: Option<LineRange>,
+}
+
+#[derive(StructOpt)]
+pub struct RenderOptions {
+	/// Render with ascii characters
+	#[structopt(long, conflicts_with = "unicode")]
+	pub ascii: bool,
+	/// Render with unicode characters
+	#[structopt(long)]
+	pub unicode: bool,
+	/// Output format
+	#[structopt(long, default_value = "text", possible_values = &["text", "ansi", "svg", "html", "png"])]
+	pub format: Format,
+	/// Colours available to ansi output
+	#[structopt(long, default_value = "256", possible_values = &["16", "256", "truecolour", "truecolor"])]
+	pub colours: ColourDepth,
+	/// Style html output so that box drawing characters join up without gaps
+	#[structopt(long)]
+	pub seamless: bool,
+	/// Size in pixels of each character cell in png output, as <width>x<height>
+	#[structopt(long, default_value = "8x16")]
+	pub cell_size: CellSize,
+}
+
+pub enum Charset {
+	Ascii,
+	Unicode,
+}
+
+impl FromStr for Charset {
+	type Err = String;
+
+	fn from_str(s: &str) -> Result<Self, Self::Err> {
+		match s {
+			"ascii" => Ok(Self::Ascii),
+			"unicode" => Ok(Self::Unicode),
+			_ => Err(format!("unknown character set {}", s)),
+		}
+	}
+}
+
+pub enum Format {
+	Text,
+	Ansi,
+	Svg,
+	Html,
+	Png,
+}
+
+impl FromStr for Format {
+	type Err = String;
+
+	fn from_str(s: &str) -> Result<Self, Self::Err> {
+		match s {
+			"text" => Ok(Self::Text),
+			"ansi" => Ok(Self::Ansi),
+			"svg" => Ok(Self::Svg),
+			"html" => Ok(Self::Html),
+			"png" => Ok(Self::Png),
+			_ => Err(format!("unknown format {}", s)),
+		}
+	}
+}
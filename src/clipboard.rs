@@ -0,0 +1,42 @@
+use crossterm::Command;
+
+use std::fmt;
+
+const BASE64_ALPHABET: &[u8; 64] =
+	b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+/// Sets the system clipboard using an OSC 52 escape sequence, which terminals forward through SSH
+/// and tmux
+pub struct SetClipboard(pub String);
+
+impl Command for SetClipboard {
+	fn write_ansi(&self, f: &mut impl fmt::Write) -> fmt::Result {
+		write!(f, "\x1b]52;c;{}\x07", base64(self.0.as_bytes()))
+	}
+
+	#[cfg(windows)]
+	fn execute_winapi(&self) -> crossterm::Result<()> { Ok(()) }
+}
+
+fn base64(input: &[u8]) -> String {
+	input
+		.chunks(3)
+		.flat_map(|chunk| {
+			let bytes = [
+				chunk[0],
+				chunk.get(1).copied().unwrap_or(0),
+				chunk.get(2).copied().unwrap_or(0),
+			];
+			let group = (bytes[0] as u32) << 16 | (bytes[1] as u32) << 8 | bytes[2] as u32;
+			(0..4).map(move |n| {
+				// Pad the final group when the input doesn't divide into whole groups
+				if n > chunk.len() {
+					'='
+				}
+				else {
+					BASE64_ALPHABET[(group >> (18 - 6 * n) & 0x3f) as usize] as char
+				}
+			})
+		})
+		.collect::<String>()
+}
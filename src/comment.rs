@@ -0,0 +1,157 @@
+use std::{
+	fmt::{self, Display},
+	str::FromStr,
+};
+
+use crate::{
+	embedded::Embedded,
+	error::{Error, Result},
+};
+
+/// Comment markers, with longer markers first so that doc comments keep all of theirs
+const MARKERS: [&str; 5] = ["///", "//!", "//", "#", "*"];
+
+/// An inclusive range of lines, counting from 1
+#[derive(Clone, Copy, Debug)]
+pub struct LineRange {
+	start: usize,
+	end: usize,
+}
+
+impl FromStr for LineRange {
+	type Err = String;
+
+	fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+		let error = || format!("line range should be given as <start>-<end>: {}", s);
+		let (start, end) = s.split_once('-').unwrap_or((s, s));
+		let start = start.parse::<usize>().map_err(|_| error())?;
+		let end = end.parse::<usize>().map_err(|_| error())?;
+		if start == 0 || end < start {
+			return Err(error());
+		}
+		Ok(Self { start, end })
+	}
+}
+
+impl Display for LineRange {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		write!(f, "{}-{}", self.start, self.end)
+	}
+}
+
+/// Finds a range of commented lines, with their indentation and comment marker stripped
+pub fn find_lines(text: &str, range: &LineRange) -> Result<Embedded> {
+	let lines = text
+		.split_inclusive('\n')
+		.scan(0, |offset, line| {
+			let start = *offset;
+			*offset += line.len();
+			Some((start, line.trim_end_matches(['\n', '\r']), line.len()))
+		})
+		.collect::<Vec<_>>();
+	if range.end > lines.len() {
+		return Err(Error::LineRange(range.to_string()));
+	}
+	let selected = &lines[range.start - 1..range.end];
+
+	let prefix = selected
+		.iter()
+		.find_map(|(_, line, _)| comment_prefix(line))
+		.ok_or(Error::CommentPrefix(range.start))?;
+
+	let contents = selected
+		.iter()
+		.enumerate()
+		.map(|(offset, (_, line, _))| match line.strip_prefix(&prefix) {
+			Some(rest) => Ok(rest.to_string() + "\n"),
+			// Blank comment lines often have the trailing space of the prefix trimmed
+			None if line.trim_end() == prefix.trim_end() => Ok(String::from("\n")),
+			None => Err(Error::CommentPrefix(range.start + offset)),
+		})
+		.collect::<Result<String>>()?;
+
+	let (start, ..) = selected[0];
+	let (last, _, length) = selected[selected.len() - 1];
+	Ok(Embedded::new(text, start..last + length, contents, prefix))
+}
+
+/// The indentation and comment marker of a line, along with a single space after the marker, as long
+/// as the comment isn't blank
+fn comment_prefix(line: &str) -> Option<String> {
+	let rest = line.trim_start();
+	let indent = &line[..line.len() - rest.len()];
+	let marker = MARKERS.iter().find(|marker| rest.starts_with(**marker))?;
+	let comment = &rest[marker.len()..];
+	if comment.trim().is_empty() {
+		return None;
+	}
+	let space = if comment.starts_with(' ') { " " } else { "" };
+	Some(format!("{}{}{}", indent, marker, space))
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn lines(text: &str, range: &str) -> Result<Embedded> {
+		find_lines(text, &range.parse::<LineRange>().unwrap())
+	}
+
+	#[test]
+	fn reads_comment_prefixes() {
+		assert_eq!(comment_prefix("\t/// +--+").as_deref(), Some("\t/// "));
+		assert_eq!(comment_prefix("//! x").as_deref(), Some("//! "));
+		assert_eq!(comment_prefix("  #|").as_deref(), Some("  #"));
+		assert_eq!(comment_prefix(" * a").as_deref(), Some(" * "));
+		assert_eq!(comment_prefix("  ///   "), None);
+		assert_eq!(comment_prefix("let x = 1;"), None);
+	}
+
+	#[test]
+	fn parses_line_ranges() {
+		assert_eq!("3-5".parse::<LineRange>().unwrap().to_string(), "3-5");
+		assert_eq!("4".parse::<LineRange>().unwrap().to_string(), "4-4");
+		assert!("0-2".parse::<LineRange>().is_err());
+		assert!("5-3".parse::<LineRange>().is_err());
+		assert!("a-b".parse::<LineRange>().is_err());
+	}
+
+	#[test]
+	fn strips_comment_markers() {
+		let text = "fn f() {\n\t/// +-+\n\t///\n\t/// | |\n}\n";
+		assert_eq!(lines(text, "2-4").unwrap().contents(), "+-+\n\n| |\n");
+
+		let text = "/*\n *  a\n *   b\n */\n";
+		assert_eq!(lines(text, "2-3").unwrap().contents(), "a\n b\n");
+
+		let text = "# x\n#  y\n";
+		assert_eq!(lines(text, "1-2").unwrap().contents(), "x\n y\n");
+	}
+
+	#[test]
+	fn rejects_lines_without_the_comment_marker() {
+		assert!(matches!(
+			lines("// a\nb\n", "1-2"),
+			Err(Error::CommentPrefix(2))
+		));
+		assert!(matches!(lines("\n\n", "1-2"), Err(Error::CommentPrefix(1))));
+		assert!(matches!(lines("// a\n", "1-2"), Err(Error::LineRange(_))));
+	}
+
+	#[test]
+	fn saves_lines_with_their_prefix() {
+		let file = std::env::temp_dir().join(format!("tpaint-comment-{}.rs", std::process::id()));
+		let text = "fn f() {\r\n    //   +-+\r\n    //\r\n    //   | |\r\n}\r\n";
+		let embedded = lines(text, "2-4").unwrap();
+
+		embedded.save(&file, embedded.contents()).unwrap();
+		assert_eq!(std::fs::read_to_string(&file).unwrap(), text);
+
+		embedded.save(&file, "x\n\ny\n").unwrap();
+		assert_eq!(
+			std::fs::read_to_string(&file).unwrap(),
+			"fn f() {\r\n    //   x\r\n    //\r\n    //   y\r\n}\r\n"
+		);
+		std::fs::remove_file(file).unwrap();
+	}
+}
@@ -0,0 +1,109 @@
+use serde::{Deserialize, Serialize};
+
+use std::{
+	fs::{read_to_string, write},
+	path::Path,
+};
+
+use crate::{
+	error::{Error, Result},
+	tools::SavedTool,
+};
+
+pub const DOCUMENT_EXTENSION: &str = "tpaint";
+
+/// Raised whenever what a document holds changes. Version 2 added styles, line styles, line modes,
+/// markers, anchors and rectangle ids, all of which are `#[serde(default)]` so that version 1
+/// documents load as they are. Fields added later need a default in the same way, or a migration in
+/// `Document::load`.
+const DOCUMENT_VERSION: u32 = 2;
+
+#[derive(Serialize, Deserialize)]
+pub struct Document {
+	version: u32,
+	ascii_mode: bool,
+	tools: Vec<SavedTool>,
+}
+
+impl Document {
+	pub fn new(tools: Vec<SavedTool>, ascii_mode: bool) -> Self {
+		Self {
+			version: DOCUMENT_VERSION,
+			ascii_mode,
+			tools,
+		}
+	}
+
+	pub fn is_document(path: &Path) -> bool {
+		path.extension()
+			.map(|extension| extension == DOCUMENT_EXTENSION)
+			.unwrap_or(false)
+	}
+
+	pub fn load(input_file: &Path) -> Result<Self> {
+		let document = serde_json::from_str::<Self>(&read_to_string(input_file)?)?;
+		if !(1..=DOCUMENT_VERSION).contains(&document.version) {
+			return Err(Error::DocumentVersion(document.version));
+		}
+		Ok(document)
+	}
+
+	pub fn save(&self, output_file: &Path) -> Result<()> {
+		write(output_file, serde_json::to_string_pretty(self)?)?;
+		Ok(())
+	}
+
+	pub fn ascii_mode(&self) -> bool { self.ascii_mode }
+
+	pub fn into_tools(self) -> Vec<SavedTool> { self.tools }
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::elements::workspace::Workspace;
+
+	fn load(name: &str, text: &str) -> Result<Document> {
+		let file =
+			std::env::temp_dir().join(format!("tpaint-{}-{}.tpaint", name, std::process::id()));
+		write(&file, text).unwrap();
+		let document = Document::load(&file);
+		std::fs::remove_file(file).unwrap();
+		document
+	}
+
+	#[test]
+	fn loads_version_one_documents() {
+		let document = load(
+			"version-one",
+			r#"{
+				"version": 1,
+				"ascii_mode": false,
+				"tools": [
+					{"tool": "rectangle", "started": true, "start": [0, 0], "end": [2, 2], "complete": true},
+					{"tool": "line", "started": true, "start": [3, 1], "end": [5, 1], "complete": true},
+					{"tool": "text", "x": 0, "y": 3, "text": ["hi"], "in_progress": false, "finished": true}
+				]
+			}"#,
+		)
+		.unwrap();
+
+		let mut workspace = Workspace::new(0, 0);
+		workspace.add_document(document);
+		assert_eq!(workspace.render_to_file(false), "┏━┓\n┃ ┃╺━╸\n┗━┛\nhi\n");
+	}
+
+	#[test]
+	fn rejects_unknown_versions() {
+		for version in [0, DOCUMENT_VERSION + 1] {
+			let text = format!(
+				r#"{{"version": {}, "ascii_mode": false, "tools": []}}"#,
+				version
+			);
+			assert!(matches!(
+				load("unknown-version", &text),
+				Err(Error::DocumentVersion(v)) if v == version
+			));
+		}
+	}
+}
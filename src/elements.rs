@@ -1,3 +1,4 @@
+pub mod colour_picker;
 pub mod horizontal_scroll;
 pub mod tool_menu;
 pub mod vertical_scroll;
@@ -5,9 +6,7 @@ pub mod workspace;
 
 use crossterm::event::{KeyEvent, MouseEvent};
 
-use std::io::Stdout;
-
-use crate::{buffer::Buffer, error::Result, state::State};
+use crate::{backend::Backend, buffer::Buffer, error::Result, state::State};
 
 pub trait Element {
 	fn resize_event(&mut self, x: u16, y: u16);
@@ -18,5 +17,5 @@ pub trait Element {
 
 	fn key_event(&mut self, event: KeyEvent) -> Box<dyn Fn(&mut State)>;
 
-	fn render(&self, w: &mut Stdout, buffer: &mut Buffer, ascii_mode: bool) -> Result<()>;
+	fn render(&self, w: &mut dyn Backend, buffer: &mut Buffer, ascii_mode: bool) -> Result<()>;
 }
@@ -0,0 +1,200 @@
+use crossterm::event::{KeyEvent, MouseButton, MouseEvent, MouseEventKind};
+
+use crate::{
+	backend::Backend,
+	buffer::Buffer,
+	elements::Element,
+	error::Result,
+	style::{Colour, Style},
+	State,
+};
+
+enum PickerElement {
+	Text(&'static str),
+	Foreground(Option<Colour>),
+	Background(Option<Colour>),
+	Bold,
+	Dim,
+}
+
+impl PickerElement {
+	fn width(&self) -> usize {
+		match self {
+			Self::Text(t) => t.chars().count(),
+			_ => 1,
+		}
+	}
+}
+
+pub struct ColourPicker {
+	x: u16,
+	y: u16,
+	min_x: u16,
+	elements: Vec<PickerElement>,
+	style: Style,
+}
+
+impl ColourPicker {
+	/// Creates a picker which keeps to the right of the menu row, but never further left than `min_x`
+	pub fn new(min_x: u16, x: u16, y: u16) -> Self {
+		let mut elements = vec![PickerElement::Text("fg "), PickerElement::Foreground(None)];
+		elements.extend(
+			Colour::BASE
+				.iter()
+				.map(|colour| PickerElement::Foreground(Some(*colour))),
+		);
+		elements.push(PickerElement::Text(" bg "));
+		elements.push(PickerElement::Background(None));
+		elements.extend(
+			Colour::BASE
+				.iter()
+				.map(|colour| PickerElement::Background(Some(*colour))),
+		);
+		elements.push(PickerElement::Text(" "));
+		elements.push(PickerElement::Bold);
+		elements.push(PickerElement::Text(" "));
+		elements.push(PickerElement::Dim);
+
+		let mut new = Self {
+			x: 0,
+			y: 0,
+			min_x,
+			elements,
+			style: Style::default(),
+		};
+		new.resize_event(x, y);
+		new
+	}
+
+	fn width(&self) -> u16 { self.elements.iter().map(|e| e.width()).sum::<usize>() as u16 }
+
+	fn render_swatch(
+		&self,
+		w: &mut dyn Backend,
+		colour: Option<Colour>,
+		current: Option<Colour>,
+		ascii_mode: bool,
+	) -> Result<()> {
+		// Bright colours are picked from the swatch of their base colour
+		let selected = current.map(Colour::base) == colour;
+		let marker = match (selected, ascii_mode) {
+			(true, false) => "•",
+			(true, true) => "*",
+			(false, _) if colour.is_some() => " ",
+			(false, false) => "·",
+			(false, true) => ".",
+		};
+		w.set_style(Style {
+			background: if selected { current } else { colour },
+			..Style::default()
+		})?;
+		w.print(marker)?;
+		w.set_style(Style::default())
+	}
+
+	fn render_toggle(
+		&self,
+		w: &mut dyn Backend,
+		label: &str,
+		enabled: bool,
+		style: Style,
+	) -> Result<()> {
+		w.set_reverse(enabled)?;
+		w.set_style(style)?;
+		w.print(label)?;
+		w.set_style(Style::default())?;
+		w.set_reverse(false)
+	}
+}
+
+impl Element for ColourPicker {
+	fn resize_event(&mut self, x: u16, _: u16) {
+		// Sits at the right of the menu row, clear of the vertical scroll bar
+		self.x = x.saturating_sub(self.width() + 1).max(self.min_x);
+		self.y = 0;
+	}
+
+	fn coord_within(&self, x: u16, y: u16) -> bool {
+		(self.x <= x && x < self.x + self.width()) && self.y == y
+	}
+
+	fn mouse_event(
+		&mut self,
+		MouseEvent {
+			kind, column: x, ..
+		}: MouseEvent,
+	) -> Box<dyn Fn(&mut State)> {
+		let bright = match kind {
+			MouseEventKind::Down(MouseButton::Left) => false,
+			MouseEventKind::Down(MouseButton::Right) => true,
+			MouseEventKind::Up(_) => return Box::new(|state| state.reset_current_mouse_element()),
+			_ => return Box::new(|_| ()),
+		};
+		let pick = |colour: Option<Colour>| {
+			colour.map(|colour| {
+				if bright {
+					colour.bright()
+				}
+				else {
+					colour
+				}
+			})
+		};
+
+		let offset = x.saturating_sub(self.x) as usize;
+		let mut counter = 0;
+		for element in &self.elements {
+			let width = element.width();
+			if counter <= offset && offset < counter + width {
+				match element {
+					PickerElement::Text(_) => (),
+					PickerElement::Foreground(colour) => self.style.foreground = pick(*colour),
+					PickerElement::Background(colour) => self.style.background = pick(*colour),
+					PickerElement::Bold => self.style.bold = !self.style.bold,
+					PickerElement::Dim => self.style.dim = !self.style.dim,
+				}
+				break;
+			}
+			counter += width;
+		}
+
+		let style = self.style;
+		Box::new(move |state| state.set_workspace_style(style))
+	}
+
+	fn key_event(&mut self, _: KeyEvent) -> Box<dyn Fn(&mut State)> { Box::new(|_| ()) }
+
+	fn render(&self, w: &mut dyn Backend, _: &mut Buffer, ascii_mode: bool) -> Result<()> {
+		w.move_to(self.x, self.y)?;
+		for element in &self.elements {
+			match element {
+				PickerElement::Text(t) => w.print(t)?,
+				PickerElement::Foreground(colour) => {
+					self.render_swatch(w, *colour, self.style.foreground, ascii_mode)?
+				}
+				PickerElement::Background(colour) => {
+					self.render_swatch(w, *colour, self.style.background, ascii_mode)?
+				}
+				PickerElement::Bold => self.render_toggle(
+					w,
+					"B",
+					self.style.bold,
+					Style {
+						bold: true,
+						..Style::default()
+					},
+				)?,
+				PickerElement::Dim => self.render_toggle(
+					w,
+					"D",
+					self.style.dim,
+					Style {
+						dim: true,
+						..Style::default()
+					},
+				)?,
+			}
+		}
+		Ok(())
+	}
+}
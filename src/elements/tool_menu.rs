@@ -1,13 +1,11 @@
-use crossterm::{
-	cursor::MoveTo,
-	event::{KeyEvent, MouseButton, MouseEvent, MouseEventKind},
-	queue,
-	style::Print,
-};
+use crossterm::event::{KeyEvent, MouseButton, MouseEvent, MouseEventKind};
 
-use std::{io::Stdout, iter::once};
+use std::iter::once;
 
-use crate::{buffer::Buffer, elements::Element, error::Result, tools::ToolSelect, State};
+use crate::{
+	backend::Backend, box_drawing::LineStyle, buffer::Buffer, elements::Element, error::Result,
+	tools::ToolSelect, State,
+};
 
 enum MenuElement {
 	Divider,
@@ -19,24 +17,21 @@ enum MenuElement {
 impl MenuElement {
 	fn width(&self) -> usize {
 		match self {
-			Self::Divider => 3,
+			Self::Divider => 1,
 			Self::Text(t) => t.chars().count(),
 			Self::Tool(t, _) => t.chars().count(),
 			Self::Mode(t) => t.chars().count(),
 		}
 	}
 
-	fn render(&self, w: &mut Stdout, _: bool) -> Result<()> {
-		queue!(
-			w,
-			Print(match self {
-				Self::Divider => " | ",
-				Self::Text(t) => t,
-				Self::Tool(t, _) => t,
-				Self::Mode(t) => t,
-			})
-		)?;
-		Ok(())
+	fn render(&self, w: &mut dyn Backend, _: bool) -> Result<()> {
+		w.print(match self {
+			// Kept narrow so the colour picker fits beside the menu on an 80 column terminal
+			Self::Divider => " ",
+			Self::Text(t) => t,
+			Self::Tool(t, _) => t,
+			Self::Mode(t) => t,
+		})
 	}
 }
 
@@ -46,6 +41,9 @@ pub struct ToolMenu {
 	length: u16,
 	elements: Vec<MenuElement>,
 	selected: ToolSelect,
+	undo_depth: usize,
+	redo_depth: usize,
+	line_style: LineStyle,
 }
 
 impl ToolMenu {
@@ -68,13 +66,34 @@ impl ToolMenu {
 				MenuElement::Divider,
 				MenuElement::Tool("-", ToolSelect::Line),
 				MenuElement::Divider,
+				MenuElement::Tool("L", ToolSelect::Elbow),
+				MenuElement::Divider,
+				MenuElement::Tool("N", ToolSelect::Polyline),
+				MenuElement::Divider,
+				MenuElement::Tool("/", ToolSelect::Diagonal),
+				MenuElement::Divider,
 				MenuElement::Tool("=", ToolSelect::ThickLine),
+				MenuElement::Divider,
+				MenuElement::Tool("^", ToolSelect::Select),
 			],
 			selected: ToolSelect::None,
+			undo_depth: 0,
+			redo_depth: 0,
+			line_style: LineStyle::default(),
 		};
 		new.resize_event(x, y);
 		new
 	}
+
+	/// The width of the menu row, not including the status row below it
+	pub fn width(&self) -> u16 { self.elements.iter().map(|e| e.width()).sum::<usize>() as u16 }
+
+	pub fn update_history(&mut self, undo_depth: usize, redo_depth: usize) {
+		self.undo_depth = undo_depth;
+		self.redo_depth = redo_depth;
+	}
+
+	pub fn update_line_style(&mut self, line_style: LineStyle) { self.line_style = line_style; }
 }
 
 impl Element for ToolMenu {
@@ -85,7 +104,8 @@ impl Element for ToolMenu {
 	}
 
 	fn coord_within(&self, x: u16, y: u16) -> bool {
-		(self.x <= x && x < self.x + self.length) && self.y == y
+		// Only the menu itself, leaving the rest of the row to other elements
+		(self.x <= x && x < self.x + self.width().min(self.length)) && self.y == y
 	}
 
 	fn mouse_event(
@@ -133,19 +153,29 @@ impl Element for ToolMenu {
 
 	fn key_event(&mut self, _: KeyEvent) -> Box<dyn Fn(&mut State)> { Box::new(|_| ()) }
 
-	fn render(&self, w: &mut Stdout, _: &mut Buffer, ascii_mode: bool) -> Result<()> {
-		queue!(w, MoveTo(self.x, self.y))?;
+	fn render(&self, w: &mut dyn Backend, _: &mut Buffer, ascii_mode: bool) -> Result<()> {
+		w.move_to(self.x, self.y)?;
 		self.elements
 			.iter()
 			.map(|e| e.render(w, ascii_mode))
 			.collect::<Result<Vec<_>>>()?;
-		queue!(w, MoveTo(self.x, self.y + 1))?;
-		queue!(w, Print(self.selected.name()))?;
-		let spaces = once(' ')
-			.cycle()
-			.take(self.length as usize - self.selected.name().len())
+		w.move_to(self.x, self.y + 1)?;
+		let history = format!(
+			"{} lines | undo {} | redo {}",
+			self.line_style.name(),
+			self.undo_depth,
+			self.redo_depth
+		);
+		let status = self
+			.selected
+			.name()
+			.chars()
+			.chain(once(' ').cycle())
+			.take((self.length as usize).saturating_sub(history.chars().count()))
+			.chain(history.chars())
+			.take(self.length as usize)
 			.collect::<String>();
-		queue!(w, Print(spaces))?;
+		w.print(&status)?;
 		Ok(())
 	}
 }
@@ -1,21 +1,15 @@
-use crossterm::{
-	cursor::MoveTo,
-	event::{KeyEvent, MouseButton, MouseEvent, MouseEventKind},
-	queue,
-	style::Print,
-};
+use crossterm::event::{KeyEvent, MouseButton, MouseEvent, MouseEventKind};
 
-use std::io::Stdout;
-
-use crate::{buffer::Buffer, elements::Element, error::Result, State};
+use crate::{backend::Backend, buffer::Buffer, elements::Element, error::Result, State};
 
 pub struct VerticalScroll {
 	x: u16,
 	y: u16,
 	length: u16,
-	view_start: usize,
-	view_end: usize,
-	max_size: usize,
+	view_start: isize,
+	view_end: isize,
+	drawn_start: isize,
+	drawn_end: isize,
 }
 
 impl VerticalScroll {
@@ -26,16 +20,31 @@ impl VerticalScroll {
 			length: 0,
 			view_start: 0,
 			view_end: 0,
-			max_size: 0,
+			drawn_start: 0,
+			drawn_end: 0,
 		};
 		new.resize_event(x, y);
 		new
 	}
 
-	pub fn update_params(&mut self, view_start: usize, view_end: usize, max_size: usize) {
+	pub fn update_params(
+		&mut self,
+		view_start: isize,
+		view_end: isize,
+		drawn_start: isize,
+		drawn_end: isize,
+	) {
 		self.view_start = view_start;
 		self.view_end = view_end;
-		self.max_size = max_size;
+		self.drawn_start = drawn_start;
+		self.drawn_end = drawn_end;
+	}
+
+	/// The start and size of the scrollable range, which covers both the drawing and the view
+	fn range(&self) -> (isize, isize) {
+		let start = self.drawn_start.min(self.view_start);
+		let end = self.drawn_end.max(self.view_end);
+		(start, (end - start).max(1))
 	}
 }
 
@@ -44,7 +53,6 @@ impl Element for VerticalScroll {
 		self.x = x - 1;
 		self.y = 1;
 		self.length = y - 2;
-		self.max_size = self.length as usize;
 	}
 
 	fn coord_within(&self, x: u16, y: u16) -> bool {
@@ -59,7 +67,8 @@ impl Element for VerticalScroll {
 			MouseEventKind::Down(button) => match button {
 				MouseButton::Left => {
 					let cursor_offset = y.saturating_sub(self.y);
-					let midpoint = (self.max_size * cursor_offset as usize) / self.length as usize;
+					let (start, size) = self.range();
+					let midpoint = start + (size * cursor_offset as isize) / self.length as isize;
 
 					Box::new(move |state| state.set_workspace_view_offset_y(midpoint))
 				}
@@ -68,7 +77,8 @@ impl Element for VerticalScroll {
 			MouseEventKind::Drag(button) => match button {
 				MouseButton::Left => {
 					let cursor_offset = y.saturating_sub(self.y);
-					let midpoint = (self.max_size * cursor_offset as usize) / self.length as usize;
+					let (start, size) = self.range();
+					let midpoint = start + (size * cursor_offset as isize) / self.length as isize;
 
 					Box::new(move |state| state.set_workspace_view_offset_y(midpoint))
 				}
@@ -81,22 +91,23 @@ impl Element for VerticalScroll {
 
 	fn key_event(&mut self, _: KeyEvent) -> Box<dyn Fn(&mut State)> { Box::new(|_| ()) }
 
-	fn render(&self, w: &mut Stdout, _: &mut Buffer, _: bool) -> Result<()> {
-		let max_size = self.max_size.max(self.view_end);
-		let view_start_bar = ((self.length as usize * self.view_start) / max_size) as u16;
-		let view_end_bar = ((self.length as usize * self.view_end) / max_size) as u16;
+	fn render(&self, w: &mut dyn Backend, _: &mut Buffer, _: bool) -> Result<()> {
+		let (start, size) = self.range();
+		let length = self.length as isize;
+		let view_start_bar = ((length * (self.view_start - start)) / size) as u16;
+		let view_end_bar = ((length * (self.view_end - start)) / size) as u16;
 
 		for offset in 0..self.length {
-			queue!(w, MoveTo(self.x, self.y + offset))?;
+			w.move_to(self.x, self.y + offset)?;
 
 			if offset < view_start_bar {
-				queue!(w, Print('░'))?;
+				w.print("░")?;
 			}
 			else if offset < view_end_bar {
-				queue!(w, Print('▓'))?;
+				w.print("▓")?;
 			}
 			else {
-				queue!(w, Print('░'))?;
+				w.print("░")?;
 			}
 		}
 
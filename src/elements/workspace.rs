@@ -1,31 +1,79 @@
-use crossterm::{
-	cursor::MoveTo,
-	event::{KeyEvent, MouseButton, MouseEvent, MouseEventKind},
-	queue,
-	style::Print,
-};
+use crossterm::event::{KeyEvent, MouseButton, MouseEvent, MouseEventKind};
 
-use std::{convert::TryFrom, io::Stdout, iter::once, path::Path};
+use std::{cell::RefCell, iter::once, mem, path::Path};
 
 use crate::{
-	buffer::Buffer,
+	ansi::{self, ColourDepth},
+	backend::Backend,
+	box_drawing::LineStyle,
+	buffer::{style_runs, Buffer, Cell},
+	canvas::Canvas,
+	document::Document,
 	elements::Element,
 	error::Result,
-	tools::{block::Block, Tool, ToolSelect},
+	html,
+	png::{self, CellSize},
+	style::Style,
+	svg,
+	tools::{block::Block, erase::Erase, merge_bounding_boxes, AnchorTarget, Tool, ToolSelect},
 	State,
 };
 
+/// A change to the finished tools, holding what is needed to make it
+enum Change {
+	/// Puts a tool back at an index
+	Insert(usize, Box<dyn Tool>),
+	/// Takes away the tool at an index
+	Remove(usize),
+	/// Swaps the tool at an index for another
+	Replace(usize, Box<dyn Tool>),
+}
+
+impl Change {
+	/// Makes the change, returning the change which reverses it
+	fn apply(self, tools: &mut Vec<Box<dyn Tool>>) -> Self {
+		match self {
+			Change::Insert(index, tool) => {
+				tools.insert(index, tool);
+				Change::Remove(index)
+			}
+			Change::Remove(index) => Change::Insert(index, tools.remove(index)),
+			Change::Replace(index, tool) => {
+				Change::Replace(index, mem::replace(&mut tools[index], tool))
+			}
+		}
+	}
+}
+
 pub struct Workspace {
 	x: u16,
 	y: u16,
 	size_x: u16,
 	size_y: u16,
-	view_offset_x: usize,
-	view_offset_y: usize,
-	mouse_right_view_offset: (usize, usize),
+	view_offset_x: isize,
+	view_offset_y: isize,
+	mouse_right_view_offset: (isize, isize),
 	mouse_right_start: (u16, u16),
 	current_tool_selection: ToolSelect,
+	style: Style,
+	line_style: LineStyle,
 	previous_tools: Vec<Box<dyn Tool>>,
+	/// Changes which reverse what has been done, most recent last
+	undo_history: Vec<Change>,
+	/// Changes which make again what has been undone, most recently undone last
+	redo_history: Vec<Change>,
+	canvas: RefCell<Canvas>,
+	selected: Option<usize>,
+	selected_handle: Option<usize>,
+	/// The selected object as it was before being dragged, to be put back by undo
+	edit: Option<(usize, Box<dyn Tool>)>,
+	select_drag_position: (isize, isize),
+	/// The id the next rectangle gets, which is never given out twice so that lines anchored to an
+	/// undone rectangle find it again when it comes back
+	next_anchor_id: u64,
+	region: Option<((isize, isize), (isize, isize))>,
+	clipboard: Vec<(isize, isize, Cell)>,
+	cursor_position: (isize, isize),
 }
 
 impl Workspace {
@@ -40,96 +88,509 @@ impl Workspace {
 			mouse_right_view_offset: (0, 0),
 			mouse_right_start: (0, 0),
 			current_tool_selection: ToolSelect::None,
-			previous_tools: vec![ToolSelect::None.to_tool()],
+			style: Style::default(),
+			line_style: LineStyle::default(),
+			previous_tools: vec![ToolSelect::None.to_tool(Style::default(), LineStyle::default())],
+			undo_history: Vec::new(),
+			redo_history: Vec::new(),
+			canvas: RefCell::new(Canvas::new()),
+			selected: None,
+			selected_handle: None,
+			edit: None,
+			select_drag_position: (0, 0),
+			next_anchor_id: 0,
+			region: None,
+			clipboard: Vec::new(),
+			cursor_position: (0, 0),
 		};
 		new.resize_event(x, y);
 		new
 	}
 
-	pub fn get_parameters(&self) -> ((usize, usize), (usize, usize), (usize, usize)) {
-		let (_, max_x, _, max_y) = self
-			.previous_tools
-			.iter()
-			.map(|tool| tool.bounding_box())
-			.fold(
-				None,
-				|acc: Option<(usize, usize, usize, usize)>, new| match (acc, new) {
-					(
-						Some((min_x, max_x, min_y, max_y)),
-						Some((new_min_x, new_max_x, new_min_y, new_max_y)),
-					) => Some((
-						min_x.min(new_min_x),
-						max_x.max(new_max_x),
-						min_y.min(new_min_y),
-						max_y.max(new_max_y),
-					)),
-					(Some(old), None) => Some(old),
-					(None, Some(new)) => Some(new),
-					(None, None) => None,
-				},
-			)
-			.unwrap_or((0, 0, 0, 0));
+	pub fn get_parameters(&self) -> ((isize, isize), (isize, isize)) {
 		(
 			(self.view_offset_x, self.view_offset_y),
 			(
-				self.view_offset_x + self.size_x as usize,
-				self.view_offset_y + self.size_y as usize,
+				self.view_offset_x + self.size_x as isize,
+				self.view_offset_y + self.size_y as isize,
 			),
-			(max_x, max_y),
 		)
 	}
 
-	pub fn set_view_offset_x(&mut self, offset: usize) { self.view_offset_x = offset }
+	/// The bounding box of everything drawn
+	pub fn drawn_bounds(&self) -> (isize, isize, isize, isize) {
+		// Only tools which haven't been baked into the canvas need to be measured
+		let canvas = self.canvas.borrow();
+		self.previous_tools
+			.iter()
+			.skip(canvas.baked())
+			.map(|tool| tool.bounding_box())
+			.fold(canvas.bounding_box(), merge_bounding_boxes)
+			.unwrap_or((0, 0, 0, 0))
+	}
 
-	pub fn set_view_offset_y(&mut self, offset: usize) { self.view_offset_y = offset }
+	pub fn origin(&self) -> (u16, u16) { (self.x, self.y) }
+
+	pub fn set_view_offset_x(&mut self, offset: isize) { self.view_offset_x = offset }
+
+	pub fn set_view_offset_y(&mut self, offset: isize) { self.view_offset_y = offset }
 
 	pub fn new_tool(&mut self) {
-		if let Some(last) = self.previous_tools.last() {
-			if !last.complete() {
+		match self.previous_tools.last() {
+			Some(last) if !last.complete() => {
 				self.previous_tools.pop();
 			}
+			// A finished tool is undone by taking it away again
+			Some(_) => {
+				let index = self.previous_tools.len() - 1;
+				self.record(Change::Remove(index));
+				self.anchor(index);
+			}
+			None => (),
+		}
+		self.previous_tools.push(
+			self.current_tool_selection
+				.to_tool(self.style, self.line_style),
+		);
+	}
+
+	pub fn history_depth(&self) -> (usize, usize) {
+		(self.undo_history.len(), self.redo_history.len())
+	}
+
+	pub fn set_tool(&mut self, tool: ToolSelect) {
+		self.current_tool_selection = tool;
+		self.selected = None;
+		self.region = None;
+	}
+
+	/// Sets the style of new tools, restyling the tool being drawn and any selected object
+	pub fn set_style(&mut self, style: Style) {
+		self.style = style;
+		if let Some(last) = self.previous_tools.last_mut() {
+			if !last.complete() {
+				last.set_style(style);
+			}
+		}
+		if let Some(index) = self.selected {
+			self.previous_tools[index].set_style(style);
+			self.canvas.get_mut().invalidate();
 		}
-		self.previous_tools
-			.push(self.current_tool_selection.to_tool());
 	}
 
-	pub fn set_tool(&mut self, tool: ToolSelect) { self.current_tool_selection = tool; }
+	pub fn line_style(&self) -> LineStyle { self.line_style }
+
+	/// Sets the line style of new tools, restyling the tool being drawn and any selected object
+	pub fn set_line_style(&mut self, line_style: LineStyle) {
+		self.line_style = line_style;
+		if let Some(last) = self.previous_tools.last_mut() {
+			if !last.complete() {
+				last.set_line_style(line_style);
+			}
+		}
+		if let Some(index) = self.selected {
+			self.previous_tools[index].set_line_style(line_style);
+			self.canvas.get_mut().invalidate();
+		}
+	}
 
 	pub fn render_to_file(&self, ascii_mode: bool) -> String {
-		let mut buffer = Buffer::new();
-		buffer.new_frame();
+		// Convert each line to String and write out to file
+		self.composite(ascii_mode)
+			.trimmed_lines()
+			.into_iter()
+			.map(|line| line + "\n")
+			.collect::<String>()
+	}
+
+	/// Renders with escape sequences for the colours and attributes of each cell
+	pub fn render_to_ansi(&self, ascii_mode: bool, depth: ColourDepth) -> String {
+		ansi::export(&self.composite(ascii_mode).trimmed_cells(), depth)
+	}
+
+	/// Renders as an svg image, with box drawing characters as vector lines
+	pub fn render_to_svg(&self, ascii_mode: bool) -> String {
+		svg::export(&self.composite(ascii_mode).trimmed_cells())
+	}
+
+	/// Renders as a `<pre>` block for embedding in a web page
+	pub fn render_to_html(&self, ascii_mode: bool, seamless: bool) -> String {
+		html::export(&self.composite(ascii_mode).trimmed_cells(), seamless)
+	}
+
+	/// Renders as a png image drawn with a bundled bitmap font
+	pub fn render_to_png(&self, ascii_mode: bool, cell_size: CellSize) -> Vec<u8> {
+		png::export(&self.composite(ascii_mode).trimmed_cells(), cell_size)
+	}
+
+	/// Loads a document or plain text file, returning whether it was saved in ascii mode
+	pub fn load(&mut self, input_file: &Path) -> Result<bool> {
+		if Document::is_document(input_file) {
+			let document = Document::load(input_file)?;
+			let ascii_mode = document.ascii_mode();
+			self.add_document(document);
+			Ok(ascii_mode)
+		}
+		else {
+			self.add_block(Block::new(input_file)?);
+			Ok(false)
+		}
+	}
+
+	pub fn add_block(&mut self, block: Block) {
+		self.discard_incomplete_tool();
+		self.previous_tools.push(Box::new(block));
+		self.previous_tools.push(
+			self.current_tool_selection
+				.to_tool(self.style, self.line_style),
+		);
+	}
+
+	pub fn add_document(&mut self, document: Document) {
+		self.discard_incomplete_tool();
+		self.previous_tools.extend(
+			document
+				.into_tools()
+				.into_iter()
+				.map(|tool| tool.into_tool()),
+		);
+
+		// New ids can't be mistaken for any in the document, even those of missing rectangles
+		let loaded_ids = self
+			.previous_tools
+			.iter()
+			.flat_map(|tool| tool.anchor_id().into_iter().chain(tool.anchored_ids()))
+			.max();
+		if let Some(id) = loaded_ids {
+			self.next_anchor_id = self.next_anchor_id.max(id + 1);
+		}
+
+		// Rectangles saved before lines could be anchored to them don't have ids yet
+		for index in 0..self.previous_tools.len() {
+			self.give_anchor_id(index);
+		}
+
+		// New input goes to a fresh tool rather than the last object loaded
+		self.previous_tools.push(
+			self.current_tool_selection
+				.to_tool(self.style, self.line_style),
+		);
+	}
+
+	pub fn to_document(&self, ascii_mode: bool) -> Document {
+		// Anchors are only kept to rectangles which are drawn, or which undo or redo can bring back
+		let history = self
+			.undo_history
+			.iter()
+			.chain(&self.redo_history)
+			.filter_map(|change| match change {
+				Change::Insert(_, tool) | Change::Replace(_, tool) => Some(tool),
+				Change::Remove(_) => None,
+			});
+		let ids = self
+			.previous_tools
+			.iter()
+			.chain(history)
+			.filter_map(|tool| tool.anchor_id())
+			.collect::<Vec<_>>();
+
+		let tools = self
+			.previous_tools
+			.iter()
+			.filter(|tool| tool.complete())
+			.filter_map(|tool| {
+				let mut tool = tool.save()?.into_tool();
+				tool.keep_anchors(&ids);
+				tool.save()
+			})
+			.collect::<Vec<_>>();
+		Document::new(tools, ascii_mode)
+	}
+
+	/// Copies the selected region, returning it as text for the system clipboard
+	pub fn copy_region(&mut self, ascii_mode: bool) -> Option<String> {
+		let (min_x, max_x, min_y, max_y) = self.region_bounds()?;
+		let buffer = self.composite(ascii_mode);
+		self.clipboard = (min_y..=max_y)
+			.flat_map(|y| (min_x..=max_x).map(move |x| (x, y)))
+			.map(|(x, y)| (x - min_x, y - min_y, buffer.get_cell(x, y)))
+			.filter(|(_, _, cell)| !cell.is_blank())
+			.collect::<Vec<_>>();
+
+		let text = (min_y..=max_y)
+			.map(|y| {
+				(min_x..=max_x)
+					.map(|x| buffer.get_point(x, y))
+					.collect::<String>()
+					.trim_end()
+					.to_string()
+			})
+			.collect::<Vec<_>>()
+			.join("\n");
+		Some(text)
+	}
+
+	pub fn cut_region(&mut self, ascii_mode: bool) -> Option<String> {
+		let (min_x, max_x, min_y, max_y) = self.region_bounds()?;
+		let text = self.copy_region(ascii_mode);
+		let points = (min_y..=max_y)
+			.flat_map(|y| (min_x..=max_x).map(move |x| (x, y)))
+			.collect::<Vec<_>>();
+		self.commit(Box::new(Erase::from_points(points)));
+		self.region = None;
+		text
+	}
 
-		// Write to buffer in chronological order
+	pub fn paste(&mut self) {
+		if self.clipboard.is_empty() {
+			return;
+		}
+
+		self.paste_block(Block::from_cells(self.clipboard.clone()));
+	}
+
+	pub fn paste_text(&mut self, text: &str) {
+		self.paste_block(Block::from_text(&line_breaks(text)));
+	}
+
+	/// Pastes into the tool being drawn, such as text being typed
+	pub fn paste_into_tool(&mut self, text: &str) {
+		let current_tool = self.previous_tools.last_mut().unwrap();
+		current_tool.paste(&line_breaks(text));
+	}
+
+	pub fn delete_selected(&mut self) {
+		if let Some(index) = self.selected.take() {
+			let tool = self.previous_tools.remove(index);
+			self.record(Change::Insert(index, tool));
+			self.reroute();
+			self.canvas.get_mut().invalidate();
+		}
+	}
+
+	pub fn undo(&mut self) {
+		if let Some(change) = self.undo_history.pop() {
+			let reverse = self.apply(change);
+			self.redo_history.push(reverse);
+		}
+	}
+
+	pub fn redo(&mut self) {
+		if let Some(change) = self.redo_history.pop() {
+			let reverse = self.apply(change);
+			self.undo_history.push(reverse);
+		}
+	}
+
+	/// Makes a change from the history, returning the change which reverses it
+	fn apply(&mut self, change: Change) -> Change {
+		self.canvas.get_mut().invalidate();
+		self.selected = None;
+		self.region = None;
+		self.discard_incomplete_tool();
+		let reverse = change.apply(&mut self.previous_tools);
+		self.reroute();
+		self.previous_tools.push(
+			self.current_tool_selection
+				.to_tool(self.style, self.line_style),
+		);
+		reverse
+	}
+
+	/// Records the change which reverses something just done, which can no longer be redone over
+	fn record(&mut self, reverse: Change) {
+		self.undo_history.push(reverse);
+		self.redo_history.clear();
+	}
+
+	fn commit(&mut self, tool: Box<dyn Tool>) {
+		self.discard_incomplete_tool();
+		self.previous_tools.push(tool);
+		self.record(Change::Remove(self.previous_tools.len() - 1));
+		self.previous_tools.push(
+			self.current_tool_selection
+				.to_tool(self.style, self.line_style),
+		);
+	}
+
+	fn paste_block(&mut self, mut block: Block) {
+		let (cursor_x, cursor_y) = self.cursor_position;
+		block.translate(cursor_x, cursor_y);
+		self.commit(Box::new(block));
+
+		// Select the pasted block so that it can be dragged into place
+		if let ToolSelect::Select = self.current_tool_selection {
+			self.selected = Some(self.previous_tools.len() - 2);
+			self.region = None;
+		}
+	}
+
+	fn composite(&self, ascii_mode: bool) -> Buffer {
+		let mut canvas = self.canvas.borrow_mut();
+		canvas.update(&self.previous_tools, ascii_mode);
+
+		let mut buffer = canvas.buffer().clone();
 		self.previous_tools
 			.iter()
+			.skip(canvas.baked())
 			.for_each(|tool| tool.render(&mut buffer, ascii_mode));
 
-		// Convert each line to String and write out to file
 		buffer
-			.into_inner()
-			.into_iter()
-			.flat_map(|line| line.into_iter().chain(once('\n')))
-			.collect::<String>()
 	}
 
-	pub fn add_file_block(&mut self, input_file: &Path) -> Result<()> {
-		self.previous_tools.push(Box::new(Block::new(input_file)?));
-		Ok(())
+	/// The ids and bounding boxes of finished tools which lines can be anchored to
+	fn anchor_targets(&self) -> Vec<AnchorTarget> {
+		self.previous_tools
+			.iter()
+			.filter(|tool| tool.complete())
+			.filter_map(|tool| Some((tool.anchor_id()?, tool.bounding_box()?)))
+			.collect()
 	}
 
-	pub fn undo(&mut self) {
-		self.previous_tools.pop();
-		self.previous_tools.pop();
-		self.new_tool();
+	/// Gives a tool an id to be anchored to, if it can have one and doesn't already
+	fn give_anchor_id(&mut self, index: usize) {
+		let tool = &mut self.previous_tools[index];
+		tool.set_anchor_id(self.next_anchor_id);
+		if tool.anchor_id() == Some(self.next_anchor_id) {
+			self.next_anchor_id += 1;
+		}
+	}
+
+	/// Gives a newly finished tool an id to be anchored to, and anchors its own ends
+	fn anchor(&mut self, index: usize) {
+		self.give_anchor_id(index);
+		let targets = self.anchor_targets();
+		self.previous_tools[index].attach(&targets);
+	}
+
+	/// Moves the ends of lines to follow whatever they are anchored to
+	fn reroute(&mut self) {
+		let targets = self.anchor_targets();
+		for tool in &mut self.previous_tools {
+			tool.reroute(&targets);
+		}
+	}
+
+	fn region_bounds(&self) -> Option<(isize, isize, isize, isize)> {
+		self.region.map(|((start_x, start_y), (end_x, end_y))| {
+			(
+				start_x.min(end_x),
+				start_x.max(end_x),
+				start_y.min(end_y),
+				start_y.max(end_y),
+			)
+		})
+	}
+
+	fn select_mouse_event(
+		&mut self,
+		x: isize,
+		y: isize,
+		kind: MouseEventKind,
+	) -> Box<dyn Fn(&mut State)> {
+		match kind {
+			MouseEventKind::Down(_) => {
+				self.select_drag_position = (x, y);
+
+				// Grab a handle of the already selected object before picking a new one
+				self.selected_handle = self.selected.and_then(|index| {
+					self.previous_tools[index]
+						.handles()
+						.into_iter()
+						.position(|handle| handle == (x, y))
+				});
+				if self.selected_handle.is_some() {
+					return Box::new(|_| ());
+				}
+
+				// Pick the topmost object under the cursor
+				self.selected = self
+					.previous_tools
+					.iter()
+					.enumerate()
+					.rev()
+					.filter(|(_, tool)| tool.complete() && tool.selectable())
+					.find(|(_, tool)| match tool.bounding_box() {
+						Some((min_x, max_x, min_y, max_y)) => {
+							(min_x <= x && x <= max_x) && (min_y <= y && y <= max_y)
+						}
+						None => false,
+					})
+					.map(|(index, _)| index);
+
+				// Start a region selection when clicking on empty canvas
+				self.region = match self.selected {
+					Some(_) => None,
+					None => Some(((x, y), (x, y))),
+				};
+				Box::new(|_| ())
+			}
+			MouseEventKind::Drag(_) => {
+				// Dragging without moving doesn't change anything
+				if self.selected.is_some() && (x, y) == self.select_drag_position {
+					return Box::new(|_| ());
+				}
+
+				if let Some(index) = self.selected {
+					if self.edit.is_none() {
+						self.edit = self.previous_tools[index]
+							.save()
+							.map(|original| (index, original.into_tool()));
+					}
+					let tool = &mut self.previous_tools[index];
+					let before = (tool.bounding_box(), tool.handles());
+					if let Some(handle) = self.selected_handle {
+						tool.move_handle(handle, x, y);
+					}
+					else {
+						let (last_x, last_y) = self.select_drag_position;
+						tool.translate(x - last_x, y - last_y);
+					}
+					self.select_drag_position = (x, y);
+
+					// The baked canvas only goes stale once the object has actually moved or resized
+					if (tool.bounding_box(), tool.handles()) != before {
+						self.canvas.get_mut().invalidate();
+						self.reroute();
+					}
+				}
+				else if let Some((start, _)) = self.region {
+					self.region = Some((start, (x, y)));
+				}
+				Box::new(|_| ())
+			}
+			MouseEventKind::Up(_) => {
+				self.selected_handle = None;
+				if let Some((index, original)) = self.edit.take() {
+					// A line dropped onto the border of a rectangle is anchored to it
+					self.anchor(index);
+					self.record(Change::Replace(index, original));
+					self.canvas.get_mut().invalidate();
+				}
+				Box::new(|state| state.reset_current_mouse_element())
+			}
+			_ => Box::new(|_| ()),
+		}
+	}
+
+	fn discard_incomplete_tool(&mut self) {
+		if let Some(last) = self.previous_tools.last() {
+			if !last.complete() {
+				self.previous_tools.pop();
+			}
+		}
 	}
 }
 
+/// Terminals commonly send pasted line breaks as carriage returns
+fn line_breaks(text: &str) -> String { text.replace("\r\n", "\n").replace('\r', "\n") }
+
 impl Element for Workspace {
 	fn resize_event(&mut self, x: u16, y: u16) {
 		self.x = 0;
 		self.y = 2;
-		self.size_x = x - 1;
-		self.size_y = y - 3;
+		self.size_x = x.saturating_sub(1);
+		self.size_y = y.saturating_sub(3);
 	}
 
 	fn coord_within(&self, x: u16, y: u16) -> bool {
@@ -153,8 +614,14 @@ impl Element for Workspace {
 						return Box::new(|_| ());
 					}
 
-					let global_x = self.view_offset_x as isize + x as isize - self.x as isize;
-					let global_y = self.view_offset_y as isize + y as isize - self.y as isize;
+					let global_x = self.view_offset_x + x as isize - self.x as isize;
+					let global_y = self.view_offset_y + y as isize - self.y as isize;
+
+					self.cursor_position = (global_x, global_y);
+
+					if let ToolSelect::Select = self.current_tool_selection {
+						return self.select_mouse_event(global_x, global_y, kind);
+					}
 
 					let current_tool = self.previous_tools.last_mut().unwrap();
 
@@ -174,8 +641,12 @@ impl Element for Workspace {
 						return Box::new(|_| ());
 					}
 
-					let global_x = self.view_offset_x as isize + x as isize - self.x as isize;
-					let global_y = self.view_offset_y as isize + y as isize - self.y as isize;
+					let global_x = self.view_offset_x + x as isize - self.x as isize;
+					let global_y = self.view_offset_y + y as isize - self.y as isize;
+
+					if let ToolSelect::Select = self.current_tool_selection {
+						return self.select_mouse_event(global_x, global_y, kind);
+					}
 
 					let current_tool = self.previous_tools.last_mut().unwrap();
 
@@ -190,13 +661,9 @@ impl Element for Workspace {
 
 					let offset_y = start_y as isize - y as isize;
 
-					let new_view_x = (start_offset_x as isize).saturating_add(offset_x);
-
-					let new_view_y = (start_offset_y as isize).saturating_add(offset_y);
+					self.view_offset_x = start_offset_x.saturating_add(offset_x);
 
-					self.view_offset_x = usize::try_from(new_view_x).unwrap_or(0);
-
-					self.view_offset_y = usize::try_from(new_view_y).unwrap_or(0);
+					self.view_offset_y = start_offset_y.saturating_add(offset_y);
 
 					Box::new(|_| ())
 				}
@@ -204,16 +671,37 @@ impl Element for Workspace {
 			},
 			MouseEventKind::Up(button) => match button {
 				MouseButton::Left => {
-					let global_x = self.view_offset_x as isize + x as isize - self.x as isize;
-					let global_y = self.view_offset_y as isize + y as isize - self.y as isize;
+					let global_x = self.view_offset_x + x as isize - self.x as isize;
+					let global_y = self.view_offset_y + y as isize - self.y as isize;
+
+					if let ToolSelect::Select = self.current_tool_selection {
+						return self.select_mouse_event(global_x, global_y, kind);
+					}
 
 					let current_tool = self.previous_tools.last_mut().unwrap();
 
 					Box::new(current_tool.mouse_event(global_x, global_y, kind))
 				}
-				MouseButton::Right => Box::new(|_| ()),
+				MouseButton::Right => Box::new(|state| state.reset_current_mouse_element()),
 				_ => Box::new(|_| ()),
 			},
+			// Only reaches the workspace while a tool is still being drawn between clicks
+			MouseEventKind::Moved => {
+				if !self.coord_within(x, y) {
+					return Box::new(|_| ());
+				}
+
+				let global_x = self.view_offset_x + x as isize - self.x as isize;
+				let global_y = self.view_offset_y + y as isize - self.y as isize;
+
+				if let ToolSelect::Select = self.current_tool_selection {
+					return Box::new(|_| ());
+				}
+
+				let current_tool = self.previous_tools.last_mut().unwrap();
+
+				Box::new(current_tool.mouse_event(global_x, global_y, kind))
+			}
 			_ => Box::new(|_| ()),
 		}
 	}
@@ -224,34 +712,83 @@ impl Element for Workspace {
 		Box::new(current_tool.key_event(event))
 	}
 
-	fn render(&self, w: &mut Stdout, buffer: &mut Buffer, ascii_mode: bool) -> Result<()> {
+	fn render(&self, w: &mut dyn Backend, buffer: &mut Buffer, ascii_mode: bool) -> Result<()> {
 		let buffer_size_x = self.size_x as usize;
 		let buffer_size_y = self.size_y as usize;
 
 		let min_x = self.view_offset_x;
 		let min_y = self.view_offset_y;
 
-		let max_x = self.view_offset_x + buffer_size_x;
-		let max_y = self.view_offset_y + buffer_size_y;
+		let max_x = self.view_offset_x + buffer_size_x as isize;
+		let max_y = self.view_offset_y + buffer_size_y as isize;
+
+		let mut canvas = self.canvas.borrow_mut();
+		canvas.update(&self.previous_tools, ascii_mode);
 
 		buffer.new_frame_bounded(min_x, min_y);
+		buffer.copy_bounded(canvas.buffer(), min_x, max_x, min_y, max_y);
 
-		// Write to buffer in chronological order
+		// Only the tool still being drawn is rendered on top of the canvas
 		self.previous_tools
 			.iter()
+			.skip(canvas.baked())
 			.for_each(|tool| tool.render_bounded(min_x, max_x, min_y, max_y, buffer, ascii_mode));
 
-		let lines = buffer.output(buffer_size_x);
+		let rows = buffer.output(buffer_size_x, buffer_size_y);
 
-		// Render buffer
-		for y in 0..buffer_size_y {
-			queue!(w, MoveTo(self.x, self.y + y as u16))?;
-			if let Some(line) = lines.get(y) {
-				queue!(w, Print(line))?;
+		// Render buffer, changing style only between runs of differently styled cells
+		for (y, row) in rows.iter().enumerate() {
+			w.move_to(self.x, self.y + y as u16)?;
+			for run in style_runs(row) {
+				w.set_style(run[0].style)?;
+				w.print(&run.iter().map(|cell| cell.c).collect::<String>())?;
+			}
+		}
+		w.set_style(Style::default())?;
+
+		// Highlight the selected object or region
+		if let Some((select_min_x, select_max_x, select_min_y, select_max_y)) = self
+			.selected
+			.and_then(|index| self.previous_tools[index].bounding_box())
+			.or_else(|| self.region_bounds())
+		{
+			let highlight_min_x = select_min_x.max(min_x);
+			let highlight_max_x = (select_max_x + 1).min(max_x);
+			for y in select_min_y.max(min_y)..(select_max_y + 1).min(max_y) {
+				let highlight = rows
+					.get((y - min_y) as usize)
+					.map(|row| row.as_slice())
+					.unwrap_or(&[])
+					.iter()
+					.map(|cell| cell.c)
+					.chain(once(' ').cycle())
+					.skip((highlight_min_x - min_x) as usize)
+					.take((highlight_max_x - highlight_min_x).max(0) as usize)
+					.collect::<String>();
+				w.move_to(
+					self.x + (highlight_min_x - min_x) as u16,
+					self.y + (y - min_y) as u16,
+				)?;
+				w.set_reverse(true)?;
+				w.print(&highlight)?;
+				w.set_reverse(false)?;
 			}
-			else {
-				let line = once(' ').cycle().take(buffer_size_x).collect::<String>();
-				queue!(w, Print(line))?;
+		}
+
+		// Draw resize handles of the selected object
+		if let Some(index) = self.selected {
+			for (handle_x, handle_y) in self.previous_tools[index]
+				.handles()
+				.into_iter()
+				.filter(|(x, y)| (min_x <= *x && *x < max_x) && (min_y <= *y && *y < max_y))
+			{
+				w.move_to(
+					self.x + (handle_x - min_x) as u16,
+					self.y + (handle_y - min_y) as u16,
+				)?;
+				w.set_reverse(true)?;
+				w.print(if ascii_mode { "o" } else { "●" })?;
+				w.set_reverse(false)?;
 			}
 		}
 
@@ -0,0 +1,126 @@
+use std::{
+	fmt::{self, Display},
+	fs::{read_to_string, write},
+	iter::repeat,
+	ops::Range,
+	path::Path,
+};
+
+use crate::{
+	comment::{self, LineRange},
+	error::Result,
+	markdown::{self, BlockSelector},
+};
+
+/// Where in a larger file the drawing being edited lives
+#[derive(Clone, Debug)]
+pub enum Region {
+	Block(BlockSelector),
+	Lines(LineRange),
+}
+
+impl Display for Region {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		match self {
+			Self::Block(selector) => write!(f, "{}", selector),
+			Self::Lines(range) => write!(f, "{}", range),
+		}
+	}
+}
+
+impl Region {
+	pub fn load(&self, input_file: &Path) -> Result<Embedded> {
+		let text = read_to_string(input_file)?;
+		match self {
+			Self::Block(selector) => markdown::find_block(&text, selector),
+			Self::Lines(range) => comment::find_lines(&text, range),
+		}
+	}
+}
+
+/// A drawing embedded in a larger file, holding on to the text around it so that the drawing can be
+/// written back without touching anything else
+pub struct Embedded {
+	before: String,
+	contents: String,
+	after: String,
+	/// Put back at the start of each line when saving, such as indentation or a comment marker
+	prefix: String,
+	newline: &'static str,
+}
+
+impl Embedded {
+	/// Splits `text` around the lines in `range`, given their contents with `prefix` already
+	/// stripped from each line
+	pub fn new(text: &str, range: Range<usize>, contents: String, mut prefix: String) -> Self {
+		let newline =
+			if text[..range.start].ends_with("\r\n") || text[range.clone()].contains("\r\n") {
+				"\r\n"
+			}
+			else {
+				"\n"
+			};
+
+		// Rendering trims blank lines around the drawing, so they are left in the surrounding text
+		let lengths = text[range.clone()]
+			.split_inclusive('\n')
+			.map(str::len)
+			.collect::<Vec<_>>();
+		let lines = contents.lines().collect::<Vec<_>>();
+		let blank = |line: &&str| line.trim().is_empty();
+		let first = lines
+			.iter()
+			.position(|line| !blank(line))
+			.unwrap_or(lines.len());
+		let last = lines
+			.iter()
+			.rposition(|line| !blank(line))
+			.map(|last| last + 1)
+			.unwrap_or(first);
+		let start = range.start + lengths[..first].iter().sum::<usize>();
+		let end = range.end - lengths[last..].iter().sum::<usize>();
+		let lines = &lines[first..last];
+
+		// Rendering also trims the drawing's left margin, so it is kept with the prefix instead
+		let margin = lines
+			.iter()
+			.filter(|line| !blank(line))
+			.map(|line| line.len() - line.trim_start_matches(' ').len())
+			.min()
+			.unwrap_or(0);
+		let contents = lines
+			.iter()
+			.map(|line| line.get(margin..).unwrap_or("").to_string() + "\n")
+			.collect();
+		prefix.extend(repeat(' ').take(margin));
+
+		Self {
+			before: text[..start].to_string(),
+			contents,
+			after: text[end..].to_string(),
+			prefix,
+			newline,
+		}
+	}
+
+	pub fn contents(&self) -> &str { &self.contents }
+
+	/// Writes the file back with new contents in place of the drawing's
+	pub fn save(&self, output_file: &Path, contents: &str) -> Result<()> {
+		let mut output = self.before.clone();
+		for line in contents.lines() {
+			// Blank lines don't keep trailing whitespace from the prefix
+			if line.is_empty() {
+				output.push_str(self.prefix.trim_end());
+			}
+			else {
+				output.push_str(&self.prefix);
+				output.push_str(line);
+			}
+			output.push_str(self.newline);
+		}
+		output.push_str(&self.after);
+		write(output_file, output)?;
+		Ok(())
+	}
+}
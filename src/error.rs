@@ -5,7 +5,17 @@ pub type Result<T> = std::result::Result<T, Error>;
 #[derive(Debug, Error)]
 pub enum Error {
 	#[error("IO error")]
-	IOError(#[from] std::io::Error),
-	#[error("crossterm error")]
-	Disconnect(#[from] crossterm::ErrorKind),
+	Io(#[from] std::io::Error),
+	#[error("document format error")]
+	Document(#[from] serde_json::Error),
+	#[error("unsupported document version {0}")]
+	DocumentVersion(u32),
+	#[error("no fenced code block matching {0}")]
+	FencedBlock(String),
+	#[error("lines {0} are outside of the file")]
+	LineRange(String),
+	#[error("line {0} does not start with the comment prefix")]
+	CommentPrefix(usize),
+	#[error("script error on line {0}: {1}")]
+	Script(usize, String),
 }
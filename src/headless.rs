@@ -0,0 +1,188 @@
+use crossterm::event::{
+	Event, KeyCode, KeyEvent, KeyModifiers, MouseButton, MouseEvent, MouseEventKind,
+};
+
+use std::path::PathBuf;
+
+pub use crate::{
+	ansi::ColourDepth,
+	backend::GridBackend,
+	embedded::Region,
+	markdown::BlockSelector,
+	style::{Colour, Style},
+};
+
+use crate::{
+	buffer::Buffer,
+	error::{Error, Result},
+	state::State,
+	tools::ToolSelect,
+	DEFAULT_FILE_NAME,
+};
+
+/// An editor driven by events rather than a terminal, with its screen kept in memory
+pub struct Session {
+	state: State,
+	backend: GridBackend,
+	buffer: Buffer,
+}
+
+impl Session {
+	pub fn new(width: u16, height: u16) -> Result<Self> {
+		let state = State::new(width, height, PathBuf::from(DEFAULT_FILE_NAME), false, None)?;
+		Self::with_state(state, width, height)
+	}
+
+	/// Opens a file as `tpaint <file>` would
+	pub fn open(width: u16, height: u16, file: PathBuf) -> Result<Self> {
+		Self::with_state(State::new(width, height, file, true, None)?, width, height)
+	}
+
+	/// Opens a drawing embedded in a larger file, as `tpaint <file> --block` would
+	pub fn open_region(width: u16, height: u16, file: PathBuf, region: Region) -> Result<Self> {
+		let state = State::new(width, height, file, true, Some(region))?;
+		Self::with_state(state, width, height)
+	}
+
+	fn with_state(state: State, width: u16, height: u16) -> Result<Self> {
+		let mut session = Self {
+			state,
+			backend: GridBackend::new(width, height),
+			buffer: Buffer::new(),
+		};
+		session
+			.state
+			.draw_frame(&mut session.backend, &mut session.buffer)?;
+		Ok(session)
+	}
+
+	pub fn handle_event(&mut self, event: Event) -> Result<()> {
+		if let Event::Resize(x, y) = event {
+			self.backend.resize(x, y);
+		}
+		self.state.handle_event(event)?;
+		self.state.draw_frame(&mut self.backend, &mut self.buffer)
+	}
+
+	/// Replays an event script, one command per line:
+	///
+	/// - `tool <name>` selects a tool, e.g. `tool thick_line`
+	/// - `down <x> <y>`, `drag <x> <y>` and `up <x> <y>` send left mouse events at canvas coordinates
+	/// - `key <key>` presses a single character or a named key such as `esc`, optionally prefixed with `ctrl+`
+	/// - `type <text>` presses each character of the text in turn
+	/// - `paste <text>` sends a bracketed paste, with `\n` for line breaks
+	///
+	/// Blank lines and lines starting with `#` are ignored.
+	pub fn replay(&mut self, script: &str) -> Result<()> {
+		for (number, line) in script.lines().enumerate() {
+			let line = line.trim();
+			if line.is_empty() || line.starts_with('#') {
+				continue;
+			}
+
+			let error = |message: &str| Error::Script(number + 1, message.to_string());
+			let (command, argument) = match line.find(' ') {
+				Some(index) => (&line[..index], &line[index + 1..]),
+				None => (line, ""),
+			};
+
+			match command {
+				"tool" => {
+					let tool = tool_from_name(argument).ok_or_else(|| error("unknown tool"))?;
+					self.state.reset_current_mouse_element();
+					self.state.set_workspace_tool(tool);
+					self.state.draw_frame(&mut self.backend, &mut self.buffer)?;
+				}
+				"down" | "drag" | "up" => {
+					let coords = argument
+						.split_whitespace()
+						.map(|n| n.parse::<u16>())
+						.collect::<std::result::Result<Vec<_>, _>>()
+						.map_err(|_| error("invalid coordinate"))?;
+					let (x, y) = match coords.as_slice() {
+						[x, y] => (*x, *y),
+						_ => return Err(error("expected two coordinates")),
+					};
+					let kind = match command {
+						"down" => MouseEventKind::Down(MouseButton::Left),
+						"drag" => MouseEventKind::Drag(MouseButton::Left),
+						_ => MouseEventKind::Up(MouseButton::Left),
+					};
+					let (origin_x, origin_y) = self.state.workspace_origin();
+					self.handle_event(Event::Mouse(MouseEvent {
+						kind,
+						column: origin_x + x,
+						row: origin_y + y,
+						modifiers: KeyModifiers::NONE,
+					}))?;
+				}
+				"key" => {
+					let (modifiers, name) = match argument.strip_prefix("ctrl+") {
+						Some(name) => (KeyModifiers::CONTROL, name),
+						None => (KeyModifiers::NONE, argument),
+					};
+					let code = key_from_name(name).ok_or_else(|| error("unknown key"))?;
+					self.handle_event(Event::Key(KeyEvent::new(code, modifiers)))?;
+				}
+				"type" => {
+					for c in argument.chars() {
+						self.handle_event(Event::Key(KeyEvent::new(
+							KeyCode::Char(c),
+							KeyModifiers::NONE,
+						)))?;
+					}
+				}
+				"paste" => self.handle_event(Event::Paste(argument.replace("\\n", "\n")))?,
+				_ => return Err(error("unknown command")),
+			}
+		}
+		Ok(())
+	}
+
+	pub fn screen(&self) -> &GridBackend { &self.backend }
+
+	pub fn render_to_file(&self, ascii_mode: bool) -> String {
+		self.state.render_to_file(ascii_mode)
+	}
+
+	pub fn render_to_ansi(&self, ascii_mode: bool, depth: ColourDepth) -> String {
+		self.state.render_to_ansi(ascii_mode, depth)
+	}
+
+	pub fn should_exit(&self) -> bool { self.state.should_exit() }
+}
+
+fn tool_from_name(name: &str) -> Option<ToolSelect> {
+	match name {
+		"none" => Some(ToolSelect::None),
+		"freehand" => Some(ToolSelect::Freehand),
+		"erase" => Some(ToolSelect::Erase),
+		"rectangle" => Some(ToolSelect::Rectangle),
+		"text" => Some(ToolSelect::Text),
+		"line" => Some(ToolSelect::Line),
+		"elbow" => Some(ToolSelect::Elbow),
+		"polyline" => Some(ToolSelect::Polyline),
+		"diagonal" => Some(ToolSelect::Diagonal),
+		"thick_line" => Some(ToolSelect::ThickLine),
+		"select" => Some(ToolSelect::Select),
+		_ => None,
+	}
+}
+
+fn key_from_name(name: &str) -> Option<KeyCode> {
+	match name {
+		"esc" => Some(KeyCode::Esc),
+		"enter" => Some(KeyCode::Enter),
+		"backspace" => Some(KeyCode::Backspace),
+		"delete" => Some(KeyCode::Delete),
+		"tab" => Some(KeyCode::Tab),
+		"space" => Some(KeyCode::Char(' ')),
+		_ => {
+			let mut chars = name.chars();
+			match (chars.next(), chars.next()) {
+				(Some(c), None) => Some(KeyCode::Char(c)),
+				_ => None,
+			}
+		}
+	}
+}
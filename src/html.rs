@@ -0,0 +1,95 @@
+use std::fmt::Write;
+
+use crate::{
+	buffer::{style_runs, Cell},
+	markup::escape_markup,
+	style::Style,
+};
+
+/// Keeps lines touching and stops fonts from joining glyphs, so that box drawing has no gaps
+const SEAMLESS_CSS: &str = "line-height: 1; letter-spacing: 0; font-variant-ligatures: none";
+
+/// Writes rows of cells as a self-contained `<pre>` block, with a span for each run of styled cells
+pub fn export(rows: &[Vec<Cell>], seamless: bool) -> String {
+	let mut output = String::new();
+	if seamless {
+		let _ = write!(output, r#"<pre style="{}">"#, SEAMLESS_CSS);
+	}
+	else {
+		output.push_str("<pre>");
+	}
+
+	for row in rows {
+		for run in style_runs(row) {
+			let text = escape_markup(&run.iter().map(|cell| cell.c).collect::<String>());
+			let style = css(run[0].style);
+			if style.is_empty() {
+				output.push_str(&text);
+			}
+			else {
+				let _ = write!(output, r#"<span style="{}">{}</span>"#, style, text);
+			}
+		}
+		output.push('\n');
+	}
+
+	output.push_str("</pre>\n");
+	output
+}
+
+fn css(style: Style) -> String {
+	let mut properties = Vec::new();
+	if let Some(colour) = style.foreground {
+		properties.push(format!("color: {}", colour.hex()));
+	}
+	if let Some(colour) = style.background {
+		properties.push(format!("background-color: {}", colour.hex()));
+	}
+	if style.bold {
+		properties.push(String::from("font-weight: bold"));
+	}
+	if style.dim {
+		properties.push(String::from("opacity: 0.5"));
+	}
+	properties.join("; ")
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::style::Colour;
+
+	fn cell(c: char, style: Style) -> Cell { Cell { c, style } }
+
+	#[test]
+	fn writes_styled_runs_as_escaped_spans() {
+		let red = Style {
+			foreground: Some(Colour::Red),
+			bold: true,
+			..Style::default()
+		};
+		let rows = vec![vec![
+			cell('<', Style::default()),
+			cell('a', red),
+			cell('&', red),
+			cell('>', Style::default()),
+		]];
+		assert_eq!(
+			export(&rows, false),
+			concat!(
+				"<pre>&lt;",
+				r#"<span style="color: #cd0000; font-weight: bold">a&amp;</span>"#,
+				"&gt;\n</pre>\n"
+			)
+		);
+	}
+
+	#[test]
+	fn adds_seamless_css() {
+		let rows = vec![vec![cell('─', Style::default())]];
+		assert_eq!(
+			export(&rows, true),
+			format!("<pre style=\"{}\">─\n</pre>\n", SEAMLESS_CSS)
+		);
+	}
+}
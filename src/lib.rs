@@ -1,62 +1,143 @@
+mod ansi;
+mod backend;
 mod box_drawing;
 mod buffer;
+mod canvas;
+mod cli;
+mod clipboard;
+mod comment;
+mod document;
 mod elements;
+mod embedded;
 mod error;
+mod html;
+mod markdown;
+mod markup;
+mod png;
 mod state;
+mod style;
+mod svg;
 mod tools;
 
+pub mod headless;
+
 use crossterm::{
 	cursor::{Hide, Show},
-	event::{read, DisableMouseCapture, EnableMouseCapture},
+	event::{
+		read, DisableBracketedPaste, DisableMouseCapture, EnableBracketedPaste, EnableMouseCapture,
+		Event,
+	},
 	queue,
 	style::ResetColor,
 	terminal::{
-		disable_raw_mode, enable_raw_mode, Clear, ClearType, EnterAlternateScreen,
-		LeaveAlternateScreen,
+		disable_raw_mode, enable_raw_mode, size, EnterAlternateScreen, LeaveAlternateScreen,
 	},
 };
 
+use structopt::StructOpt;
+
 use std::{
-	env::args,
 	io::{Stdout, Write},
-	path::PathBuf,
+	iter::once,
+	path::{Path, PathBuf},
 };
 
-use crate::{buffer::Buffer, error::Result, state::State};
+use crate::{
+	backend::TerminalBackend,
+	buffer::Buffer,
+	cli::{Charset, Command, EditOptions, Format, Options, RenderOptions},
+	elements::workspace::Workspace,
+	embedded::Region,
+	error::Result,
+	state::State,
+	tools::block::Block,
+};
 
-const DEFAULT_FILE_NAME: &str = "output.txt";
+const DEFAULT_FILE_NAME: &str = "output.tpaint";
 
 pub fn run(w: &mut Stdout) -> Result<()> {
-	let args = args().collect::<Vec<_>>();
-	let (file_name, load) = args
-		.get(1)
-		.map(|path| (PathBuf::from(path), true))
+	match Options::from_args().command {
+		None => edit(w, EditOptions::default()),
+		Some(Command::Edit(options)) => edit(w, options),
+		Some(Command::File(args)) => edit(
+			w,
+			EditOptions::from_iter(once(String::from("tpaint")).chain(args)),
+		),
+		Some(Command::Render { file, options }) => render(w, &file, options),
+		Some(Command::Convert { file, to }) => convert(w, &file, to),
+	}
+}
+
+fn render(w: &mut Stdout, file: &Path, options: RenderOptions) -> Result<()> {
+	let mut workspace = Workspace::new(0, 0);
+	let saved_ascii_mode = workspace.load(file)?;
+	let ascii_mode = options.ascii || (saved_ascii_mode && !options.unicode);
+	let output = match options.format {
+		Format::Text => workspace.render_to_file(ascii_mode).into_bytes(),
+		Format::Ansi => workspace
+			.render_to_ansi(ascii_mode, options.colours)
+			.into_bytes(),
+		Format::Svg => workspace.render_to_svg(ascii_mode).into_bytes(),
+		Format::Html => workspace
+			.render_to_html(ascii_mode, options.seamless)
+			.into_bytes(),
+		Format::Png => workspace.render_to_png(ascii_mode, options.cell_size),
+	};
+	w.write_all(&output)?;
+	Ok(())
+}
+
+fn convert(w: &mut Stdout, file: &Path, to: Charset) -> Result<()> {
+	let mut block = Block::new(file)?;
+	let ascii_mode = match to {
+		Charset::Ascii => true,
+		Charset::Unicode => {
+			block.infer_box_drawing();
+			false
+		}
+	};
+	let mut workspace = Workspace::new(0, 0);
+	workspace.add_block(block);
+	write!(w, "{}", workspace.render_to_file(ascii_mode))?;
+	Ok(())
+}
+
+fn edit(w: &mut Stdout, EditOptions { file, block, lines }: EditOptions) -> Result<()> {
+	let region = block.map(Region::Block).or(lines.map(Region::Lines));
+	let (file_name, load) = file
+		.map(|path| (path, true))
 		.unwrap_or((PathBuf::from(DEFAULT_FILE_NAME), false));
 
-	queue!(w, EnterAlternateScreen, Hide, EnableMouseCapture)?;
+	queue!(
+		w,
+		EnterAlternateScreen,
+		Hide,
+		EnableMouseCapture,
+		EnableBracketedPaste
+	)?;
 	enable_raw_mode()?;
 
 	w.flush()?;
 
-	let mut state = State::new(file_name, load)?;
+	let (x, y) = size()?;
+	let mut state = State::new(x, y, file_name, load, region)?;
 	let mut buffer = Buffer::new();
+	let mut backend = TerminalBackend::new(&mut *w, x, y);
 
-	while state.should_exit() == false {
-		if state.should_clear() {
-			queue!(w, Clear(ClearType::All))?;
-			state.set_should_clear(false);
-		}
-
-		state.render(w, &mut buffer)?;
+	while !state.should_exit() {
+		state.draw_frame(&mut backend, &mut buffer)?;
 
-		w.flush()?;
-
-		state.handle_event(read()?)?;
+		let event = read()?;
+		if let Event::Resize(x, y) = event {
+			backend.resize(x, y);
+		}
+		state.handle_event(event)?;
 	}
 
 	queue!(
 		w,
 		ResetColor,
+		DisableBracketedPaste,
 		DisableMouseCapture,
 		Show,
 		LeaveAlternateScreen
@@ -0,0 +1,201 @@
+use std::{
+	fmt::{self, Display},
+	str::FromStr,
+};
+
+use crate::{
+	embedded::Embedded,
+	error::{Error, Result},
+};
+
+/// Picks a fenced code block by its position in the file, counting from 1, or by a word in its
+/// info string
+#[derive(Clone, Debug)]
+pub enum BlockSelector {
+	Index(usize),
+	Tag(String),
+}
+
+impl FromStr for BlockSelector {
+	type Err = String;
+
+	fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+		match s.parse::<usize>() {
+			Ok(0) => Err(String::from("blocks are numbered from 1")),
+			Ok(index) => Ok(Self::Index(index)),
+			Err(_) => Ok(Self::Tag(s.to_string())),
+		}
+	}
+}
+
+impl Display for BlockSelector {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		match self {
+			Self::Index(index) => write!(f, "{}", index),
+			Self::Tag(tag) => write!(f, "{}", tag),
+		}
+	}
+}
+
+impl BlockSelector {
+	fn matches(&self, index: usize, info: &str) -> bool {
+		match self {
+			Self::Index(wanted) => *wanted == index,
+			Self::Tag(tag) => info.split_whitespace().any(|word| word == tag),
+		}
+	}
+}
+
+struct Fence {
+	indent: usize,
+	marker: char,
+	length: usize,
+}
+
+impl Fence {
+	/// Reads an opening fence along with its info string
+	fn open(line: &str) -> Option<(Self, &str)> {
+		let (indent, rest) = split_indent(line)?;
+		let marker = rest.chars().next().filter(|c| *c == '`' || *c == '~')?;
+		let length = rest.len() - rest.trim_start_matches(marker).len();
+		let info = rest[length..].trim();
+		if length < 3 || (marker == '`' && info.contains('`')) {
+			return None;
+		}
+		Some((
+			Self {
+				indent,
+				marker,
+				length,
+			},
+			info,
+		))
+	}
+
+	fn closed_by(&self, line: &str) -> bool {
+		let rest = match split_indent(line) {
+			Some((_, rest)) => rest,
+			None => return false,
+		};
+		let length = rest.len() - rest.trim_start_matches(self.marker).len();
+		length >= self.length && rest[length..].trim().is_empty()
+	}
+}
+
+/// Splits off the up to three spaces of indentation a fence may have
+fn split_indent(line: &str) -> Option<(usize, &str)> {
+	let line = line.trim_end_matches(['\n', '\r']);
+	let rest = line.trim_start_matches(' ');
+	let indent = line.len() - rest.len();
+	(indent <= 3).then_some((indent, rest))
+}
+
+/// Finds a fenced code block, with the fence's indentation stripped from its lines
+pub fn find_block(text: &str, selector: &BlockSelector) -> Result<Embedded> {
+	let lines = text
+		.split_inclusive('\n')
+		.scan(0, |offset, line| {
+			let start = *offset;
+			*offset += line.len();
+			Some((start, line))
+		})
+		.collect::<Vec<_>>();
+
+	let mut index = 0;
+	let mut line = 0;
+	while line < lines.len() {
+		let (start, opening) = lines[line];
+		let (fence, info) = match Fence::open(opening) {
+			Some(fence) => fence,
+			None => {
+				line += 1;
+				continue;
+			}
+		};
+
+		// An unclosed fence runs to the end of the file
+		let close = (line + 1..lines.len())
+			.find(|close| fence.closed_by(lines[*close].1))
+			.unwrap_or(lines.len());
+		index += 1;
+
+		if selector.matches(index, info) {
+			let contents_start = start + opening.len();
+			let contents_end = lines
+				.get(close)
+				.map(|(start, _)| *start)
+				.unwrap_or(text.len());
+			let contents = text[contents_start..contents_end]
+				.lines()
+				.map(|line| {
+					let indent = line.len() - line.trim_start_matches(' ').len();
+					line[indent.min(fence.indent)..].to_string() + "\n"
+				})
+				.collect();
+			return Ok(Embedded::new(
+				text,
+				contents_start..contents_end,
+				contents,
+				" ".repeat(fence.indent),
+			));
+		}
+		line = close + 1;
+	}
+
+	Err(Error::FencedBlock(selector.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	const TEXT: &str = concat!(
+		"```rust\n",
+		"fn main() {}\n",
+		"```\n",
+		"not ``` a fence\n",
+		"   ````` text diagram\n",
+		"   a\n",
+		"  b\n",
+		"```\n",
+		"`````\n",
+		"~~~\n",
+		"unclosed\n",
+	);
+
+	fn contents(selector: &str) -> Result<String> {
+		let selector = selector.parse::<BlockSelector>().unwrap();
+		find_block(TEXT, &selector).map(|block| block.contents().to_string())
+	}
+
+	#[test]
+	fn selects_blocks_by_index_or_tag() {
+		assert_eq!(contents("1").unwrap(), "fn main() {}\n");
+		assert_eq!(contents("rust").unwrap(), "fn main() {}\n");
+		assert_eq!(contents("2").unwrap(), "a\nb\n```\n");
+		assert_eq!(contents("diagram").unwrap(), "a\nb\n```\n");
+		assert_eq!(contents("3").unwrap(), "unclosed\n");
+		assert!(contents("4").is_err());
+		assert!(contents("python").is_err());
+	}
+
+	#[test]
+	fn rejects_block_zero() {
+		assert!("0".parse::<BlockSelector>().is_err());
+	}
+
+	#[test]
+	fn reads_fences() {
+		assert!(Fence::open("``").is_none());
+		assert!(Fence::open("    ```").is_none());
+		assert!(Fence::open("``` a`b").is_none());
+		let (fence, info) = Fence::open("  ~~~~ a`b \r\n").unwrap();
+		assert_eq!(
+			(fence.indent, fence.marker, fence.length, info),
+			(2, '~', 4, "a`b")
+		);
+		assert!(fence.closed_by("~~~~~\n"));
+		assert!(!fence.closed_by("~~~\n"));
+		assert!(!fence.closed_by("~~~~ x\n"));
+	}
+}
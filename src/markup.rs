@@ -0,0 +1,6 @@
+/// Escapes the characters which would otherwise be read as markup in svg and html
+pub fn escape_markup(text: &str) -> String {
+	text.replace('&', "&amp;")
+		.replace('<', "&lt;")
+		.replace('>', "&gt;")
+}
@@ -0,0 +1,486 @@
+mod font;
+
+use std::str::FromStr;
+
+use crate::{
+	box_drawing::{BoxFlags, Shape, Weight},
+	buffer::Cell,
+};
+
+use self::font::{glyph, GLYPH_SIZE};
+
+type Rgb = (u8, u8, u8);
+
+/// Colours used where a cell has none, as the image has no terminal colours to fall back on
+const DEFAULT_FOREGROUND: Rgb = (0, 0, 0);
+const DEFAULT_BACKGROUND: Rgb = (255, 255, 255);
+
+/// Deflate's stored blocks hold at most this many bytes each
+const STORED_BLOCK_SIZE: usize = 0xffff;
+
+/// The size in pixels that each cell of the canvas is drawn at
+#[derive(Clone, Copy, Debug)]
+pub struct CellSize {
+	width: usize,
+	height: usize,
+}
+
+impl FromStr for CellSize {
+	type Err = String;
+
+	fn from_str(s: &str) -> Result<Self, Self::Err> {
+		let error = || format!("cell size should be given as <width>x<height>: {}", s);
+		let (width, height) = s.split_once('x').ok_or_else(error)?;
+		let width = width.parse::<usize>().map_err(|_| error())?;
+		let height = height.parse::<usize>().map_err(|_| error())?;
+		if width == 0 || height == 0 {
+			return Err(error());
+		}
+		Ok(Self { width, height })
+	}
+}
+
+struct Image {
+	width: usize,
+	height: usize,
+	pixels: Vec<Rgb>,
+}
+
+impl Image {
+	fn new(width: usize, height: usize) -> Self {
+		Self {
+			width,
+			height,
+			pixels: vec![DEFAULT_BACKGROUND; width * height],
+		}
+	}
+
+	fn fill(&mut self, x: usize, y: usize, width: usize, height: usize, colour: Rgb) {
+		for row in y..(y + height).min(self.height) {
+			for column in x..(x + width).min(self.width) {
+				self.pixels[row * self.width + column] = colour;
+			}
+		}
+	}
+
+	/// Draws a cell whose top left corner is at `(x, y)`
+	fn draw_cell(&mut self, x: usize, y: usize, size: CellSize, cell: Cell) {
+		let CellSize { width, height } = size;
+		let background = cell
+			.style
+			.background
+			.map(|c| c.rgb())
+			.unwrap_or(DEFAULT_BACKGROUND);
+		let mut foreground = cell
+			.style
+			.foreground
+			.map(|c| c.rgb())
+			.unwrap_or(DEFAULT_FOREGROUND);
+		if cell.style.dim {
+			foreground = blend(foreground, background, 2);
+		}
+		self.fill(x, y, width, height, background);
+
+		let (half_width, half_height) = (width / 2, height / 2);
+		match cell.c {
+			' ' => (),
+			'█' => self.fill(x, y, width, height, foreground),
+			'▀' => self.fill(x, y, width, half_height, foreground),
+			'▄' => self.fill(x, y + half_height, width, height - half_height, foreground),
+			'▌' => self.fill(x, y, half_width, height, foreground),
+			'▐' => self.fill(x + half_width, y, width - half_width, height, foreground),
+			'░' => self.fill(x, y, width, height, blend(foreground, background, 4)),
+			'▒' => self.fill(x, y, width, height, blend(foreground, background, 2)),
+			'▓' => self.fill(x, y, width, height, blend(background, foreground, 4)),
+			'╱' => self.draw_diagonal(x, y, size, true, foreground),
+			'╲' => self.draw_diagonal(x, y, size, false, foreground),
+			'╳' => {
+				self.draw_diagonal(x, y, size, true, foreground);
+				self.draw_diagonal(x, y, size, false, foreground);
+			}
+			'▲' | '▼' | '◀' | '▶' | '●' | '◆' => {
+				self.draw_marker(x, y, size, cell.c, foreground)
+			}
+			c => match Shape::of(c) {
+				Some(shape) if shape.rounded => self.draw_corner(x, y, size, shape, foreground),
+				Some(shape) => self.draw_box(x, y, size, shape, foreground),
+				None => self.draw_glyph(x, y, size, c, foreground, cell.style.bold),
+			},
+		}
+	}
+
+	/// Draws lines from the centre of a cell out to the middle of each edge it connects to, in the
+	/// weight and style of the character's arms
+	fn draw_box(&mut self, x: usize, y: usize, size: CellSize, shape: Shape, colour: Rgb) {
+		let CellSize { width, height } = size;
+		let light = (width / 8).max(1);
+		// The two lines of a double arm are either side of where a single line would be
+		let gap = light * 2;
+
+		for (direction, horizontal) in [
+			(BoxFlags::LEFT, true),
+			(BoxFlags::RIGHT, true),
+			(BoxFlags::UP, false),
+			(BoxFlags::DOWN, false),
+		] {
+			let thickness = match shape.arm(direction) {
+				Weight::None => continue,
+				Weight::Heavy => (width / 4).max(1),
+				_ => light,
+			};
+			let (along, across, sides) = match horizontal {
+				true => (width, height, [BoxFlags::UP, BoxFlags::DOWN]),
+				false => (height, width, [BoxFlags::LEFT, BoxFlags::RIGHT]),
+			};
+			let centre_along = along.saturating_sub(thickness) / 2;
+			let centre_across = across.saturating_sub(thickness) / 2;
+			let lines = match shape.arm(direction) {
+				Weight::Double => vec![
+					(centre_across.saturating_sub(gap), shape.inset(sides[0])),
+					(centre_across + gap, shape.inset(sides[1])),
+				],
+				_ => vec![(centre_across, 0)],
+			};
+
+			for (offset, inset) in lines {
+				let inset = inset * gap as isize;
+				let (start, end) = match direction {
+					BoxFlags::LEFT | BoxFlags::UP => {
+						(0, (centre_along + thickness) as isize - inset)
+					}
+					_ => (centre_along as isize + inset, along as isize),
+				};
+				for position in start.max(0) as usize..(end.max(0) as usize).min(along) {
+					// Dashes are counted from the edge of the image so that they carry on evenly
+					// from one cell to the next
+					let from_edge = position + if horizontal { x } else { y };
+					if shape.dashed && from_edge % (light * 3) >= light * 2 {
+						continue;
+					}
+					match horizontal {
+						true => self.fill(x + position, y + offset, 1, thickness, colour),
+						false => self.fill(x + offset, y + position, thickness, 1, colour),
+					}
+				}
+			}
+		}
+	}
+
+	/// Draws a rounded corner as a quarter of an ellipse joining the middles of the two edges it
+	/// connects to
+	fn draw_corner(&mut self, x: usize, y: usize, size: CellSize, shape: Shape, colour: Rgb) {
+		let CellSize { width, height } = size;
+		let thickness = (width / 8).max(1);
+		let line_x = width.saturating_sub(thickness) / 2;
+		let line_y = height.saturating_sub(thickness) / 2;
+		// The ellipse is centred on the corner of the cell between the two arms
+		let (centre_x, radius_x, sign_x) = match shape.arm(BoxFlags::RIGHT) {
+			Weight::None => (0.0, line_x as f64, 1.0),
+			_ => (width as f64, (width - line_x) as f64, -1.0),
+		};
+		let (centre_y, radius_y, sign_y) = match shape.arm(BoxFlags::DOWN) {
+			Weight::None => (0.0, line_y as f64, 1.0),
+			_ => (height as f64, (height - line_y) as f64, -1.0),
+		};
+
+		let steps = (width + height) * 2;
+		for step in 0..=steps {
+			let angle = std::f64::consts::FRAC_PI_2 * step as f64 / steps as f64;
+			let column = centre_x + sign_x * radius_x * angle.cos();
+			let row = centre_y + sign_y * radius_y * angle.sin();
+			let column = (column.round() as usize).min(width - thickness);
+			let row = (row.round() as usize).min(height - thickness);
+			self.fill(x + column, y + row, thickness, thickness, colour);
+		}
+	}
+
+	/// Draws a line from one corner of a cell to the opposite corner, going up to the right when
+	/// `rising`
+	fn draw_diagonal(&mut self, x: usize, y: usize, size: CellSize, rising: bool, colour: Rgb) {
+		let CellSize { width, height } = size;
+		let thickness = (width / 4).max(1);
+		for row in 0..height {
+			let column = (row * width / height).min(width - thickness);
+			let column = if rising {
+				width - thickness - column
+			}
+			else {
+				column
+			};
+			self.fill(x + column, y + row, thickness, 1, colour);
+		}
+	}
+
+	/// Draws a line end marker as a filled shape in a square at the centre of the cell, as the font
+	/// only has ascii glyphs
+	fn draw_marker(&mut self, x: usize, y: usize, size: CellSize, c: char, colour: Rgb) {
+		let CellSize { width, height } = size;
+		let inside = |u: f64, v: f64| match c {
+			'▲' => v <= 0.7 && u.abs() <= (0.7 + v) / 2.0,
+			'▼' => v >= -0.7 && u.abs() <= (0.7 - v) / 2.0,
+			'◀' => u <= 0.7 && v.abs() <= (0.7 + u) / 2.0,
+			'▶' => u >= -0.7 && v.abs() <= (0.7 - u) / 2.0,
+			'●' => u * u + v * v <= 0.5,
+			_ => u.abs() + v.abs() <= 1.0,
+		};
+
+		// Pixels are placed from -1 to 1 across the largest square that fits in the cell
+		let half = width.min(height) as f64 / 2.0;
+		for row in 0..height {
+			for column in 0..width {
+				let u = (column as f64 + 0.5 - width as f64 / 2.0) / half;
+				let v = (row as f64 + 0.5 - height as f64 / 2.0) / half;
+				if inside(u, v) {
+					self.fill(x + column, y + row, 1, 1, colour);
+				}
+			}
+		}
+	}
+
+	/// Draws a glyph from the font, scaled up by whole pixels and centred within the cell
+	fn draw_glyph(&mut self, x: usize, y: usize, size: CellSize, c: char, colour: Rgb, bold: bool) {
+		let scale = (size.width / GLYPH_SIZE)
+			.min(size.height / GLYPH_SIZE)
+			.max(1);
+		let x = x + size.width.saturating_sub(GLYPH_SIZE * scale) / 2;
+		let y = y + size.height.saturating_sub(GLYPH_SIZE * scale) / 2;
+		// Bold glyphs are smeared one pixel to the right
+		let width = scale + bold as usize;
+		for (row, bits) in glyph(c).iter().enumerate() {
+			for column in (0..GLYPH_SIZE).filter(|column| bits & (1 << column) != 0) {
+				self.fill(x + column * scale, y + row * scale, width, scale, colour);
+			}
+		}
+	}
+}
+
+/// Mixes one part of `a` with `parts - 1` parts of `b`
+fn blend(a: Rgb, b: Rgb, parts: u16) -> Rgb {
+	let mix = |a: u8, b: u8| ((a as u16 + b as u16 * (parts - 1)) / parts) as u8;
+	(mix(a.0, b.0), mix(a.1, b.1), mix(a.2, b.2))
+}
+
+/// Draws rows of cells as a png image, using a bundled bitmap font for text and drawing box
+/// drawing and block characters directly
+pub fn export(rows: &[Vec<Cell>], size: CellSize) -> Vec<u8> {
+	let columns = rows.iter().map(|row| row.len()).max().unwrap_or(0).max(1);
+	let mut image = Image::new(columns * size.width, rows.len().max(1) * size.height);
+	for (y, row) in rows.iter().enumerate() {
+		for (x, cell) in row.iter().enumerate() {
+			image.draw_cell(x * size.width, y * size.height, size, *cell);
+		}
+	}
+	encode(&image)
+}
+
+fn encode(image: &Image) -> Vec<u8> {
+	// Each scanline starts with a byte choosing its filter, which is always none
+	let mut raw = Vec::with_capacity(image.height * (image.width * 3 + 1));
+	for row in image.pixels.chunks(image.width) {
+		raw.push(0);
+		for (r, g, b) in row {
+			raw.extend_from_slice(&[*r, *g, *b]);
+		}
+	}
+
+	let mut header = Vec::new();
+	header.extend_from_slice(&(image.width as u32).to_be_bytes());
+	header.extend_from_slice(&(image.height as u32).to_be_bytes());
+	// 8 bits per channel, truecolour, default compression, filtering and no interlacing
+	header.extend_from_slice(&[8, 2, 0, 0, 0]);
+
+	let mut output = b"\x89PNG\r\n\x1a\n".to_vec();
+	chunk(&mut output, b"IHDR", &header);
+	chunk(&mut output, b"IDAT", &zlib(&raw));
+	chunk(&mut output, b"IEND", &[]);
+	output
+}
+
+fn chunk(output: &mut Vec<u8>, kind: &[u8; 4], data: &[u8]) {
+	output.extend_from_slice(&(data.len() as u32).to_be_bytes());
+	let start = output.len();
+	output.extend_from_slice(kind);
+	output.extend_from_slice(data);
+	let crc = crc32(&output[start..]);
+	output.extend_from_slice(&crc.to_be_bytes());
+}
+
+/// Wraps data in a zlib stream without compressing it
+fn zlib(data: &[u8]) -> Vec<u8> {
+	let mut output = vec![0x78, 0x01];
+	let blocks = data.chunks(STORED_BLOCK_SIZE).collect::<Vec<_>>();
+	for (index, block) in blocks.iter().enumerate() {
+		let last = index + 1 == blocks.len();
+		let length = block.len() as u16;
+		output.push(last as u8);
+		output.extend_from_slice(&length.to_le_bytes());
+		output.extend_from_slice(&(!length).to_le_bytes());
+		output.extend_from_slice(block);
+	}
+	output.extend_from_slice(&adler32(data).to_be_bytes());
+	output
+}
+
+fn crc32(data: &[u8]) -> u32 {
+	let mut crc = !0u32;
+	for byte in data {
+		crc ^= *byte as u32;
+		for _ in 0..8 {
+			crc = if crc & 1 != 0 {
+				(crc >> 1) ^ 0xedb8_8320
+			}
+			else {
+				crc >> 1
+			};
+		}
+	}
+	!crc
+}
+
+fn adler32(data: &[u8]) -> u32 {
+	let (mut a, mut b) = (1u32, 0u32);
+	for byte in data {
+		a = (a + *byte as u32) % 65521;
+		b = (b + a) % 65521;
+	}
+	(b << 16) | a
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn computes_checksums() {
+		assert_eq!(crc32(b"IEND"), 0xae42_6082);
+		assert_eq!(crc32(b"123456789"), 0xcbf4_3926);
+		assert_eq!(adler32(b"Wikipedia"), 0x11e6_0398);
+		assert_eq!(adler32(&[]), 1);
+	}
+
+	#[test]
+	fn splits_zlib_data_into_stored_blocks() {
+		let data = vec![7; STORED_BLOCK_SIZE + 2];
+		let stream = zlib(&data);
+
+		assert_eq!(stream[..2], [0x78, 0x01]);
+		assert_eq!(stream[2..7], [0, 0xff, 0xff, 0, 0]);
+		let second = 7 + STORED_BLOCK_SIZE;
+		assert_eq!(stream[second..second + 5], [1, 2, 0, 0xfd, 0xff]);
+		assert_eq!(stream[second + 5..second + 7], [7, 7]);
+		assert_eq!(stream[second + 7..], adler32(&data).to_be_bytes());
+	}
+
+	#[test]
+	fn frames_chunks_with_length_and_crc() {
+		let mut output = Vec::new();
+		chunk(&mut output, b"IEND", &[]);
+		assert_eq!(
+			output,
+			[0, 0, 0, 0, b'I', b'E', b'N', b'D', 0xae, 0x42, 0x60, 0x82]
+		);
+
+		let image = export(&[], CellSize::from_str("3x5").unwrap());
+		assert_eq!(image[..8], *b"\x89PNG\r\n\x1a\n");
+		// The header chunk starts with the width and height of a single blank cell
+		assert_eq!(image[16..24], [0, 0, 0, 3, 0, 0, 0, 5]);
+		assert!(image.ends_with(&output));
+	}
+
+	/// Draws a single black cell and returns which of its pixels were drawn on
+	fn drawn(c: char, size: &str) -> Vec<Vec<bool>> {
+		let size = CellSize::from_str(size).unwrap();
+		let mut image = Image::new(size.width, size.height);
+		image.draw_cell(
+			0,
+			0,
+			size,
+			Cell {
+				c,
+				style: Default::default(),
+			},
+		);
+		image
+			.pixels
+			.chunks(size.width)
+			.map(|row| {
+				row.iter()
+					.map(|pixel| *pixel != DEFAULT_BACKGROUND)
+					.collect()
+			})
+			.collect()
+	}
+
+	#[test]
+	fn draws_line_weights_and_styles() {
+		let light = drawn('─', "8x16");
+		assert!(light[7].iter().all(|pixel| *pixel));
+		assert_eq!(light.iter().filter(|row| row[0]).count(), 1);
+
+		let heavy = drawn('━', "8x16");
+		assert_eq!(heavy.iter().filter(|row| row[0]).count(), 2);
+
+		let double = drawn('═', "8x16");
+		assert!(double[5].iter().all(|pixel| *pixel));
+		assert!(double[9].iter().all(|pixel| *pixel));
+		assert!(!double[7][0]);
+
+		let dashed = drawn('┄', "8x16");
+		assert_eq!(
+			dashed[7],
+			[true, true, false, true, true, false, true, true]
+		);
+	}
+
+	#[test]
+	fn draws_double_corners_meeting() {
+		let corner = drawn('╔', "8x16");
+		// The outer lines meet above and to the left of the middle, and the inner ones below and
+		// to the right
+		assert!(corner[5][1] && corner[5][7] && corner[15][1]);
+		assert!(corner[9][5] && corner[9][7] && corner[15][5]);
+		assert!(!corner[5][0] && !corner[9][4] && !corner[4][1]);
+	}
+
+	#[test]
+	fn draws_rounded_corners_as_curves() {
+		let corner = drawn('╭', "8x16");
+		assert!(corner[15][3] && corner[7][7]);
+		// The square corner between the two ends is left out
+		assert!(!corner[7][3] && !corner[15][7]);
+	}
+
+	#[test]
+	fn draws_markers_as_shapes() {
+		let right = drawn('▶', "8x16");
+		assert!(right[7][1] && right[7][5] && right[5][1]);
+		assert!(!right[5][3] && !right[7][7] && !right[4][1]);
+
+		let up = drawn('▲', "8x16");
+		assert!(up[6][3] && up[10][1] && up[10][6]);
+		assert!(!up[6][1] && !up[6][6] && !up[11][3]);
+
+		let dot = drawn('●', "8x16");
+		assert!(dot[7][1] && dot[5][3]);
+		assert!(!dot[5][1] && !dot[4][3]);
+
+		let diamond = drawn('◆', "8x16");
+		assert!(diamond[7][0] && diamond[4][3]);
+		assert!(!diamond[4][2] && !diamond[3][3]);
+
+		// None of them fall back to the font's question mark
+		let question = drawn('?', "8x16");
+		for marker in ['▲', '▼', '◀', '▶', '●', '◆'] {
+			assert_ne!(drawn(marker, "8x16"), question);
+		}
+	}
+
+	#[test]
+	fn parses_cell_sizes() {
+		let size = CellSize::from_str("8x16").unwrap();
+		assert_eq!((size.width, size.height), (8, 16));
+		assert!(CellSize::from_str("8").is_err());
+		assert!(CellSize::from_str("0x16").is_err());
+		assert!(CellSize::from_str("8xa").is_err());
+	}
+}
@@ -1,18 +1,23 @@
-use crossterm::{
-	event::{Event, KeyCode, KeyEvent, KeyModifiers, MouseEvent, MouseEventKind},
-	terminal::size,
-};
+use crossterm::event::{Event, KeyCode, KeyEvent, KeyModifiers, MouseEvent, MouseEventKind};
 
-use std::{fs::write, io::Stdout, path::PathBuf};
+use std::{
+	fs::{metadata, read_to_string, write},
+	path::{Path, PathBuf},
+};
 
 use crate::{
+	ansi::{ColourDepth, ANSI_EXTENSION},
+	backend::Backend,
 	buffer::Buffer,
+	document::{Document, DOCUMENT_EXTENSION},
 	elements::{
-		horizontal_scroll::HorizontalScroll, tool_menu::ToolMenu, vertical_scroll::VerticalScroll,
-		workspace::Workspace, Element,
+		colour_picker::ColourPicker, horizontal_scroll::HorizontalScroll, tool_menu::ToolMenu,
+		vertical_scroll::VerticalScroll, workspace::Workspace, Element,
 	},
+	embedded::{Embedded, Region},
 	error::Result,
-	tools::ToolSelect,
+	style::Style,
+	tools::{block::Block, ToolSelect},
 };
 
 pub enum CurrentElement {
@@ -20,57 +25,131 @@ pub enum CurrentElement {
 	Workspace,
 	VerticalScroll,
 	HorizontalScroll,
+	ToolMenu,
 	Element(usize),
 }
 
+/// Whether to open the document saved alongside a file rather than the file itself, which is when
+/// the document was saved after the file was last written or still exports to the file's text, or
+/// the text of the region being edited, so that edits made to the file elsewhere aren't lost
+fn open_saved_document(
+	document_file: &Path,
+	file: &Path,
+	embedded: &Option<Embedded>,
+) -> Result<bool> {
+	if !document_file.exists() {
+		return Ok(false);
+	}
+	if !file.exists() || metadata(document_file)?.modified()? > metadata(file)?.modified()? {
+		return Ok(true);
+	}
+
+	let mut saved = Workspace::new(0, 0);
+	let ascii_mode = saved.load(document_file)?;
+	let (exported, current) = match embedded {
+		Some(embedded) => (
+			saved.render_to_file(ascii_mode),
+			embedded.contents().to_string(),
+		),
+		None if file.extension().and_then(|e| e.to_str()) == Some(ANSI_EXTENSION) => (
+			saved.render_to_ansi(ascii_mode, ColourDepth::default()),
+			read_to_string(file)?,
+		),
+		None => (saved.render_to_file(ascii_mode), read_to_string(file)?),
+	};
+	Ok(exported == current)
+}
+
 pub struct State {
 	should_exit: bool,
 	should_clear: bool,
+	clipboard: Option<String>,
 	workspace: Workspace,
 	current_mouse_element: CurrentElement,
 	vertical_scroll: VerticalScroll,
 	horizontal_scroll: HorizontalScroll,
+	tool_menu: ToolMenu,
 	elements: Vec<Box<dyn Element>>,
-	output_file: PathBuf,
+	document_file: PathBuf,
+	export_file: PathBuf,
+	/// The part of a larger file being edited, which exports are written back into
+	embedded: Option<Embedded>,
 	ascii_mode: bool,
 }
 
 impl State {
-	pub fn new(output_file: PathBuf, load: bool) -> Result<Self> {
-		let (x, y) = size().unwrap();
+	pub fn new(x: u16, y: u16, file: PathBuf, load: bool, region: Option<Region>) -> Result<Self> {
 		let mut workspace = Workspace::new(x, y);
-		if load {
-			workspace.add_file_block(&output_file)?;
+		let embedded = match &region {
+			Some(region) => Some(region.load(&file)?),
+			None => None,
+		};
+		// Plain text files are exported in place, with the editable document saved alongside
+		let (document_file, export_file) = if let Some(region) = &region {
+			// Each drawing embedded in a file gets a document of its own
+			(
+				file.with_extension(format!("{}.{}", region, DOCUMENT_EXTENSION)),
+				file.clone(),
+			)
+		}
+		else if Document::is_document(&file) {
+			(file.clone(), file.with_extension("txt"))
 		}
+		else {
+			(file.with_extension(DOCUMENT_EXTENSION), file.clone())
+		};
+
+		let ascii_mode = match (&embedded, load) {
+			// A document saved alongside holds the objects the file was exported from, so it is
+			// opened rather than overwritten by the next save, unless the file was changed after it
+			(_, true)
+				if document_file != file
+					&& open_saved_document(&document_file, &file, &embedded)? =>
+			{
+				workspace.load(&document_file)?
+			}
+			// Drawings which are all ascii are kept that way, while empty regions are drawn in as usual
+			(Some(embedded), _) => {
+				let contents = embedded.contents();
+				workspace.add_block(Block::from_text(contents));
+				!contents.trim().is_empty() && contents.is_ascii()
+			}
+			(None, true) => workspace.load(&file)?,
+			(None, false) => false,
+		};
+
+		let tool_menu = ToolMenu::new(x, y);
+		let colour_picker = ColourPicker::new(tool_menu.width() + 3, x, y);
+
 		Ok(Self {
 			should_exit: false,
 			should_clear: false,
+			clipboard: None,
 			workspace,
 			current_mouse_element: CurrentElement::None,
 			vertical_scroll: VerticalScroll::new(x, y),
 			horizontal_scroll: HorizontalScroll::new(x, y),
-			elements: vec![Box::new(ToolMenu::new(x, y))],
-			output_file,
-			ascii_mode: false,
+			tool_menu,
+			elements: vec![Box::new(colour_picker)],
+			document_file,
+			export_file,
+			embedded,
+			ascii_mode,
 		})
 	}
 
 	pub fn should_exit(&self) -> bool { self.should_exit }
 
-	pub fn should_clear(&self) -> bool { self.should_clear }
-
-	pub fn set_should_clear(&mut self, should_clear: bool) { self.should_clear = should_clear; }
-
 	pub fn reset_current_mouse_element(&mut self) {
 		self.current_mouse_element = CurrentElement::None;
 		self.workspace.new_tool();
 	}
 
-	pub fn set_workspace_view_offset_x(&mut self, offset: usize) {
+	pub fn set_workspace_view_offset_x(&mut self, offset: isize) {
 		self.workspace.set_view_offset_x(offset);
 	}
 
-	pub fn set_workspace_view_offset_y(&mut self, offset: usize) {
+	pub fn set_workspace_view_offset_y(&mut self, offset: isize) {
 		self.workspace.set_view_offset_y(offset);
 	}
 
@@ -79,6 +158,18 @@ impl State {
 		self.workspace.new_tool();
 	}
 
+	pub fn set_workspace_style(&mut self, style: Style) { self.workspace.set_style(style); }
+
+	pub fn workspace_origin(&self) -> (u16, u16) { self.workspace.origin() }
+
+	pub fn render_to_file(&self, ascii_mode: bool) -> String {
+		self.workspace.render_to_file(ascii_mode)
+	}
+
+	pub fn render_to_ansi(&self, ascii_mode: bool, depth: ColourDepth) -> String {
+		self.workspace.render_to_ansi(ascii_mode, depth)
+	}
+
 	pub fn change_mode(&mut self) { self.ascii_mode = !self.ascii_mode; }
 
 	pub fn exit(&mut self) { self.should_exit = true }
@@ -87,6 +178,7 @@ impl State {
 		self.workspace.resize_event(x, y);
 		self.vertical_scroll.resize_event(x, y);
 		self.horizontal_scroll.resize_event(x, y);
+		self.tool_menu.resize_event(x, y);
 
 		for element in &mut self.elements {
 			element.resize_event(x, y);
@@ -94,10 +186,11 @@ impl State {
 		self.should_clear = true;
 	}
 
-	pub fn render(&self, w: &mut Stdout, buffer: &mut Buffer) -> Result<()> {
+	pub fn render(&self, w: &mut dyn Backend, buffer: &mut Buffer) -> Result<()> {
 		self.workspace.render(w, buffer, self.ascii_mode)?;
 		self.vertical_scroll.render(w, buffer, self.ascii_mode)?;
 		self.horizontal_scroll.render(w, buffer, self.ascii_mode)?;
+		self.tool_menu.render(w, buffer, self.ascii_mode)?;
 
 		for element in &self.elements {
 			element.render(w, buffer, self.ascii_mode)?;
@@ -106,6 +199,21 @@ impl State {
 		Ok(())
 	}
 
+	pub fn draw_frame(&mut self, w: &mut dyn Backend, buffer: &mut Buffer) -> Result<()> {
+		if self.should_clear {
+			w.clear()?;
+			self.should_clear = false;
+		}
+
+		self.render(w, buffer)?;
+
+		if let Some(text) = self.clipboard.take() {
+			w.set_clipboard(&text)?;
+		}
+
+		w.flush()
+	}
+
 	pub fn handle_event(&mut self, event: Event) -> Result<()> {
 		match event {
 			Event::Key(k) => {
@@ -113,6 +221,7 @@ impl State {
 				if let KeyEvent {
 					code: KeyCode::Char('c'),
 					modifiers: KeyModifiers::CONTROL,
+					..
 				} = k
 				{
 					self.exit()
@@ -122,20 +231,79 @@ impl State {
 						KeyEvent {
 							code: KeyCode::Char('q'),
 							modifiers: KeyModifiers::NONE,
+							..
 						} => self.exit(),
 						KeyEvent {
 							code: KeyCode::Char('s'),
 							modifiers: KeyModifiers::NONE,
+							..
 						} => self.save_file()?,
+						KeyEvent {
+							code: KeyCode::Char('e'),
+							modifiers: KeyModifiers::NONE,
+							..
+						} => self.export_file()?,
+						KeyEvent {
+							code: KeyCode::Char('a'),
+							modifiers: KeyModifiers::NONE,
+							..
+						} => {
+							self.export_ansi_file(&self.export_file.with_extension(ANSI_EXTENSION))?
+						}
+						KeyEvent {
+							code: KeyCode::Char('l'),
+							modifiers: KeyModifiers::NONE,
+							..
+						} => {
+							let line_style = self.workspace.line_style().next();
+							self.workspace.set_line_style(line_style)
+						}
 						KeyEvent {
 							code: KeyCode::Char('z'),
 							modifiers: KeyModifiers::NONE,
+							..
 						} => self.workspace.undo(),
+						KeyEvent {
+							code: KeyCode::Char('Z'),
+							modifiers: _,
+							..
+						}
+						| KeyEvent {
+							code: KeyCode::Char('y'),
+							modifiers: KeyModifiers::CONTROL,
+							..
+						} => self.workspace.redo(),
+						KeyEvent {
+							code: KeyCode::Delete,
+							modifiers: _,
+							..
+						} => self.workspace.delete_selected(),
+						KeyEvent {
+							code: KeyCode::Char('c'),
+							modifiers: KeyModifiers::NONE,
+							..
+						} => self.clipboard = self.workspace.copy_region(self.ascii_mode),
+						KeyEvent {
+							code: KeyCode::Char('C'),
+							modifiers: _,
+							..
+						} => self.clipboard = Some(self.workspace.render_to_file(self.ascii_mode)),
+						KeyEvent {
+							code: KeyCode::Char('x'),
+							modifiers: KeyModifiers::NONE,
+							..
+						} => self.clipboard = self.workspace.cut_region(self.ascii_mode),
+						KeyEvent {
+							code: KeyCode::Char('v'),
+							modifiers: KeyModifiers::NONE,
+							..
+						} => self.workspace.paste(),
 						_ => (),
 					},
 					CurrentElement::Workspace => self.workspace.key_event(k)(self),
 					CurrentElement::VerticalScroll => self.vertical_scroll.key_event(k)(self),
 					CurrentElement::HorizontalScroll => self.horizontal_scroll.key_event(k)(self),
+					CurrentElement::ToolMenu => self.tool_menu.key_event(k)(self),
 					CurrentElement::Element(index) => self.elements[index].key_event(k)(self),
 				}
 			}
@@ -167,6 +335,11 @@ impl State {
 
 								self.workspace.mouse_event(event)(self)
 							}
+							else if self.tool_menu.coord_within(x, y) {
+								self.current_mouse_element = CurrentElement::ToolMenu;
+
+								self.tool_menu.mouse_event(event)(self)
+							}
 							else {
 								// Find an element with the mouse within
 								if let Some((n, element)) = self
@@ -187,30 +360,72 @@ impl State {
 					CurrentElement::HorizontalScroll => {
 						self.horizontal_scroll.mouse_event(event)(self)
 					}
+					CurrentElement::ToolMenu => self.tool_menu.mouse_event(event)(self),
 					CurrentElement::Element(index) => self.elements[index].mouse_event(event)(self),
 				};
 			}
 
+			// Pastes go to whatever is being used, like keys, and only paste blocks when nothing is
+			Event::Paste(text) => match self.current_mouse_element {
+				CurrentElement::None => self.workspace.paste_text(&text),
+				CurrentElement::Workspace => self.workspace.paste_into_tool(&text),
+				_ => (),
+			},
+
 			Event::Resize(x, y) => self.resize(x, y),
+
+			Event::FocusGained | Event::FocusLost => (),
 		}
 
 		self.update_scrolls();
+		self.update_tool_menu();
 
 		Ok(())
 	}
 
 	fn update_scrolls(&mut self) {
-		let ((view_start_x, view_start_y), (view_end_x, view_end_y), (max_size_x, max_size_y)) =
+		let ((view_start_x, view_start_y), (view_end_x, view_end_y)) =
 			self.workspace.get_parameters();
+		let (min_x, max_x, min_y, max_y) = self.workspace.drawn_bounds();
 		self.horizontal_scroll
-			.update_params(view_start_x, view_end_x, max_size_x);
+			.update_params(view_start_x, view_end_x, min_x, max_x);
 		self.vertical_scroll
-			.update_params(view_start_y, view_end_y, max_size_y);
+			.update_params(view_start_y, view_end_y, min_y, max_y);
+	}
+
+	fn update_tool_menu(&mut self) {
+		let (undo_depth, redo_depth) = self.workspace.history_depth();
+		self.tool_menu.update_history(undo_depth, redo_depth);
+		self.tool_menu
+			.update_line_style(self.workspace.line_style());
 	}
 
 	fn save_file(&self) -> Result<()> {
+		self.workspace
+			.to_document(self.ascii_mode)
+			.save(&self.document_file)
+	}
+
+	fn export_file(&self) -> Result<()> {
 		let output = self.workspace.render_to_file(self.ascii_mode);
-		write(&self.output_file, output.as_bytes())?;
+		if let Some(embedded) = &self.embedded {
+			return embedded.save(&self.export_file, &output);
+		}
+
+		// Keep the colours of files which were styled to begin with
+		if self.export_file.extension().and_then(|e| e.to_str()) == Some(ANSI_EXTENSION) {
+			return self.export_ansi_file(&self.export_file);
+		}
+
+		write(&self.export_file, output.as_bytes())?;
+		Ok(())
+	}
+
+	fn export_ansi_file(&self, path: &Path) -> Result<()> {
+		let output = self
+			.workspace
+			.render_to_ansi(self.ascii_mode, ColourDepth::default());
+		write(path, output.as_bytes())?;
 		Ok(())
 	}
 }
@@ -0,0 +1,188 @@
+use crossterm::style::Color;
+
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Colour {
+	Black,
+	Red,
+	Green,
+	Yellow,
+	Blue,
+	Magenta,
+	Cyan,
+	White,
+	BrightBlack,
+	BrightRed,
+	BrightGreen,
+	BrightYellow,
+	BrightBlue,
+	BrightMagenta,
+	BrightCyan,
+	BrightWhite,
+	/// A colour from the 256 colour palette, beyond the sixteen named ones
+	Indexed(u8),
+	Rgb(u8, u8, u8),
+}
+
+impl Colour {
+	/// The eight base colours, in palette order
+	pub const BASE: [Colour; 8] = [
+		Colour::Black,
+		Colour::Red,
+		Colour::Green,
+		Colour::Yellow,
+		Colour::Blue,
+		Colour::Magenta,
+		Colour::Cyan,
+		Colour::White,
+	];
+
+	/// The colour at an index of the 256 colour palette
+	pub fn from_index(index: u8) -> Self {
+		match index {
+			0..=7 => Colour::BASE[index as usize],
+			8..=15 => Colour::BASE[index as usize - 8].bright(),
+			index => Colour::Indexed(index),
+		}
+	}
+
+	/// The colour's index in the 256 colour palette, if it has one
+	pub fn index(self) -> Option<u8> {
+		match self {
+			Colour::Indexed(index) => Some(index),
+			Colour::Rgb(..) => None,
+			named => {
+				let base = Colour::BASE.iter().position(|c| *c == named.base())? as u8;
+				Some(if named == named.base() {
+					base
+				}
+				else {
+					base + 8
+				})
+			}
+		}
+	}
+
+	/// The colour as a hex code, as svg and html write it
+	pub fn hex(self) -> String {
+		let (r, g, b) = self.rgb();
+		format!("#{:02x}{:02x}{:02x}", r, g, b)
+	}
+
+	/// The colour's red, green and blue components, using xterm's palette for indexed colours
+	pub fn rgb(self) -> (u8, u8, u8) {
+		match (self, self.index()) {
+			(Colour::Rgb(r, g, b), _) => (r, g, b),
+			(_, index) => palette(index.unwrap_or(0)),
+		}
+	}
+
+	pub fn bright(self) -> Self {
+		match self {
+			Colour::Black => Colour::BrightBlack,
+			Colour::Red => Colour::BrightRed,
+			Colour::Green => Colour::BrightGreen,
+			Colour::Yellow => Colour::BrightYellow,
+			Colour::Blue => Colour::BrightBlue,
+			Colour::Magenta => Colour::BrightMagenta,
+			Colour::Cyan => Colour::BrightCyan,
+			Colour::White => Colour::BrightWhite,
+			bright => bright,
+		}
+	}
+
+	pub fn base(self) -> Self {
+		match self {
+			Colour::BrightBlack => Colour::Black,
+			Colour::BrightRed => Colour::Red,
+			Colour::BrightGreen => Colour::Green,
+			Colour::BrightYellow => Colour::Yellow,
+			Colour::BrightBlue => Colour::Blue,
+			Colour::BrightMagenta => Colour::Magenta,
+			Colour::BrightCyan => Colour::Cyan,
+			Colour::BrightWhite => Colour::White,
+			base => base,
+		}
+	}
+}
+
+impl From<Colour> for Color {
+	fn from(colour: Colour) -> Self {
+		match colour {
+			Colour::Black => Color::Black,
+			Colour::Red => Color::DarkRed,
+			Colour::Green => Color::DarkGreen,
+			Colour::Yellow => Color::DarkYellow,
+			Colour::Blue => Color::DarkBlue,
+			Colour::Magenta => Color::DarkMagenta,
+			Colour::Cyan => Color::DarkCyan,
+			Colour::White => Color::Grey,
+			Colour::BrightBlack => Color::DarkGrey,
+			Colour::BrightRed => Color::Red,
+			Colour::BrightGreen => Color::Green,
+			Colour::BrightYellow => Color::Yellow,
+			Colour::BrightBlue => Color::Blue,
+			Colour::BrightMagenta => Color::Magenta,
+			Colour::BrightCyan => Color::Cyan,
+			Colour::BrightWhite => Color::White,
+			Colour::Indexed(index) => Color::AnsiValue(index),
+			Colour::Rgb(r, g, b) => Color::Rgb { r, g, b },
+		}
+	}
+}
+
+fn palette(index: u8) -> (u8, u8, u8) {
+	const BASIC: [(u8, u8, u8); 16] = [
+		(0, 0, 0),
+		(205, 0, 0),
+		(0, 205, 0),
+		(205, 205, 0),
+		(0, 0, 238),
+		(205, 0, 205),
+		(0, 205, 205),
+		(229, 229, 229),
+		(127, 127, 127),
+		(255, 0, 0),
+		(0, 255, 0),
+		(255, 255, 0),
+		(92, 92, 255),
+		(255, 0, 255),
+		(0, 255, 255),
+		(255, 255, 255),
+	];
+	const LEVELS: [u8; 6] = [0, 95, 135, 175, 215, 255];
+
+	match index {
+		0..=15 => BASIC[index as usize],
+		// A 6x6x6 colour cube followed by a ramp of greys
+		16..=231 => {
+			let cube = index - 16;
+			(
+				LEVELS[(cube / 36) as usize],
+				LEVELS[(cube / 6 % 6) as usize],
+				LEVELS[(cube % 6) as usize],
+			)
+		}
+		_ => {
+			let grey = 8 + 10 * (index - 232);
+			(grey, grey, grey)
+		}
+	}
+}
+
+/// The colours and attributes of a cell, where no colour means the terminal's default
+#[derive(Clone, Copy, Default, PartialEq, Eq, Debug, Serialize, Deserialize)]
+pub struct Style {
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	pub foreground: Option<Colour>,
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	pub background: Option<Colour>,
+	#[serde(default, skip_serializing_if = "is_false")]
+	pub bold: bool,
+	#[serde(default, skip_serializing_if = "is_false")]
+	pub dim: bool,
+}
+
+fn is_false(value: &bool) -> bool { !value }
@@ -0,0 +1,387 @@
+use std::{
+	collections::{BTreeMap, HashMap},
+	fmt::Write,
+};
+
+use crate::{
+	box_drawing::{BoxFlags, Shape, Weight},
+	buffer::Cell,
+	markup::escape_markup,
+	style::{Colour, Style},
+};
+
+const CELL_WIDTH: isize = 10;
+const CELL_HEIGHT: isize = 20;
+const FONT_SIZE: usize = 16;
+const BASELINE: isize = 15;
+const LIGHT_WIDTH: usize = 1;
+const HEAVY_WIDTH: usize = 2;
+/// How far each line of a double line is from the middle of its cell
+const DOUBLE_GAP: isize = 2;
+const DASH_ARRAY: &str = "2 3";
+
+/// How a line is painted, with all lines sharing a stroke going into one path
+#[derive(Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+struct Stroke {
+	colour: String,
+	width: usize,
+	dashed: bool,
+}
+
+impl Stroke {
+	fn new(colour: &str, weight: Weight, dashed: bool) -> Self {
+		Self {
+			colour: colour.to_string(),
+			width: match weight {
+				Weight::Heavy => HEAVY_WIDTH,
+				_ => LIGHT_WIDTH,
+			},
+			dashed,
+		}
+	}
+}
+
+/// A straight run of box drawing, at `fixed` across the direction it runs in
+struct Segment {
+	stroke: Stroke,
+	fixed: isize,
+	start: isize,
+	end: isize,
+}
+
+/// Joins the segments of a row or column into runs which are as long as possible
+#[derive(Default)]
+struct Runs {
+	runs: Vec<Segment>,
+	/// The run each stroke, line and end position could be continued from
+	ends: HashMap<(Stroke, isize, isize), usize>,
+}
+
+impl Runs {
+	fn add(&mut self, stroke: &Stroke, fixed: isize, start: isize, end: isize) {
+		let index = match self.ends.remove(&(stroke.clone(), fixed, start)) {
+			Some(index) => {
+				self.runs[index].end = end;
+				index
+			}
+			None => {
+				self.runs.push(Segment {
+					stroke: stroke.clone(),
+					fixed,
+					start,
+					end,
+				});
+				self.runs.len() - 1
+			}
+		};
+		self.ends.insert((stroke.clone(), fixed, end), index);
+	}
+
+	/// Adds the horizontal or vertical arms of the box drawing character in the cell at `(x, y)`
+	fn add_arms(&mut self, shape: Shape, colour: &str, horizontal: bool, x: isize, y: isize) {
+		let (directions, sides, centre, edges, fixed) = if horizontal {
+			(
+				[BoxFlags::LEFT, BoxFlags::RIGHT],
+				[BoxFlags::UP, BoxFlags::DOWN],
+				x * CELL_WIDTH + CELL_WIDTH / 2,
+				(x * CELL_WIDTH, (x + 1) * CELL_WIDTH),
+				y * CELL_HEIGHT + CELL_HEIGHT / 2,
+			)
+		}
+		else {
+			(
+				[BoxFlags::UP, BoxFlags::DOWN],
+				[BoxFlags::LEFT, BoxFlags::RIGHT],
+				y * CELL_HEIGHT + CELL_HEIGHT / 2,
+				(y * CELL_HEIGHT, (y + 1) * CELL_HEIGHT),
+				x * CELL_WIDTH + CELL_WIDTH / 2,
+			)
+		};
+		for (index, direction) in directions.iter().enumerate() {
+			let weight = shape.arm(*direction);
+			let stroke = Stroke::new(colour, weight, shape.dashed);
+			// The arm before the centre ends where the arm after it would start
+			let span = |inset: isize| match index {
+				0 => (edges.0, centre - inset),
+				_ => (centre + inset, edges.1),
+			};
+			match weight {
+				Weight::None => (),
+				Weight::Double => {
+					for (side, offset) in sides.iter().zip([-DOUBLE_GAP, DOUBLE_GAP]) {
+						let (start, end) = span(shape.inset(*side) * DOUBLE_GAP);
+						self.add(&stroke, fixed + offset, start, end);
+					}
+				}
+				_ => {
+					let (start, end) = span(0);
+					self.add(&stroke, fixed, start, end);
+				}
+			}
+		}
+	}
+}
+
+/// Draws rows of cells as an svg image, with box drawing characters as vector lines and everything
+/// else as text on a monospace grid
+pub fn export(rows: &[Vec<Cell>]) -> String {
+	let columns = rows.iter().map(|row| row.len()).max().unwrap_or(0);
+	let (width, height) = (
+		columns as isize * CELL_WIDTH,
+		rows.len() as isize * CELL_HEIGHT,
+	);
+
+	let mut backgrounds = String::new();
+	let mut glyphs = String::new();
+	let mut horizontal = Runs::default();
+	let mut vertical = Runs::default();
+	// One path per stroke holds all of its lines
+	let mut paths = BTreeMap::<Stroke, String>::new();
+
+	for (y, row) in rows.iter().enumerate() {
+		let y = y as isize;
+		for (x, cell) in row.iter().enumerate() {
+			let x = x as isize;
+			if let Some(colour) = cell.style.background {
+				let _ = write!(
+					backgrounds,
+					r#"<rect x="{}" y="{}" width="{}" height="{}" fill="{}"/>"#,
+					x * CELL_WIDTH,
+					y * CELL_HEIGHT,
+					CELL_WIDTH,
+					CELL_HEIGHT,
+					hex(Some(colour))
+				);
+			}
+
+			let colour = hex(cell.style.foreground);
+			if let Some(shape) = Shape::of(cell.c) {
+				let (centre_x, centre_y) = (
+					x * CELL_WIDTH + CELL_WIDTH / 2,
+					y * CELL_HEIGHT + CELL_HEIGHT / 2,
+				);
+				if shape.rounded {
+					// Rounded corners curve from the middle of one edge to the middle of the other
+					let edge_x = match shape.arm(BoxFlags::LEFT) {
+						Weight::None => x * CELL_WIDTH + CELL_WIDTH,
+						_ => x * CELL_WIDTH,
+					};
+					let edge_y = match shape.arm(BoxFlags::UP) {
+						Weight::None => y * CELL_HEIGHT + CELL_HEIGHT,
+						_ => y * CELL_HEIGHT,
+					};
+					let stroke = Stroke::new(&colour, Weight::Light, false);
+					let _ = write!(
+						paths.entry(stroke).or_default(),
+						"M{} {}Q{} {} {} {}",
+						edge_x,
+						centre_y,
+						centre_x,
+						centre_y,
+						centre_x,
+						edge_y
+					);
+				}
+				else {
+					horizontal.add_arms(shape, &colour, true, x, y);
+				}
+			}
+
+			if cell.c == '█' {
+				let _ = write!(
+					glyphs,
+					r#"<rect x="{}" y="{}" width="{}" height="{}" fill="{}"/>"#,
+					x * CELL_WIDTH,
+					y * CELL_HEIGHT,
+					CELL_WIDTH,
+					CELL_HEIGHT,
+					colour
+				);
+			}
+		}
+
+		// Everything else is written as runs of text sharing a style
+		let mut x = 0;
+		while x < row.len() {
+			if !is_text(row[x]) {
+				x += 1;
+				continue;
+			}
+			let start = x;
+			let style = row[x].style;
+			while x < row.len() && is_text(row[x]) && row[x].style == style {
+				x += 1;
+			}
+			let text = row[start..x].iter().map(|cell| cell.c).collect::<String>();
+			let _ = write!(
+				glyphs,
+				r#"<text x="{}" y="{}" textLength="{}" fill="{}"{}>{}</text>"#,
+				start as isize * CELL_WIDTH,
+				y * CELL_HEIGHT + BASELINE,
+				(x - start) as isize * CELL_WIDTH,
+				hex(style.foreground),
+				attributes(style),
+				escape_markup(&text)
+			);
+		}
+	}
+
+	for x in 0..columns {
+		for (y, row) in rows.iter().enumerate() {
+			let cell = row.get(x).copied().unwrap_or_default();
+			let (x, y) = (x as isize, y as isize);
+			match Shape::of(cell.c) {
+				Some(shape) if !shape.rounded => {
+					vertical.add_arms(shape, &hex(cell.style.foreground), false, x, y)
+				}
+				_ => (),
+			}
+		}
+	}
+
+	for segment in horizontal.runs {
+		let _ = write!(
+			paths.entry(segment.stroke).or_default(),
+			"M{} {}H{}",
+			segment.start,
+			segment.fixed,
+			segment.end
+		);
+	}
+	for segment in vertical.runs {
+		let _ = write!(
+			paths.entry(segment.stroke).or_default(),
+			"M{} {}V{}",
+			segment.fixed,
+			segment.start,
+			segment.end
+		);
+	}
+	let lines = paths
+		.into_iter()
+		.map(|(stroke, path)| {
+			let dashes = match stroke.dashed {
+				true => format!(r#" stroke-dasharray="{}""#, DASH_ARRAY),
+				false => String::new(),
+			};
+			format!(
+				r#"<path d="{}" stroke="{}" stroke-width="{}"{} stroke-linecap="square" fill="none"/>"#,
+				path, stroke.colour, stroke.width, dashes
+			)
+		})
+		.collect::<String>();
+
+	format!(
+		concat!(
+			r#"<svg xmlns="http://www.w3.org/2000/svg" width="{width}" height="{height}" "#,
+			r#"viewBox="0 0 {width} {height}" font-family="monospace" font-size="{font_size}">"#,
+			"\n{backgrounds}{lines}{glyphs}\n</svg>\n"
+		),
+		width = width,
+		height = height,
+		font_size = FONT_SIZE,
+		backgrounds = backgrounds,
+		lines = lines,
+		glyphs = glyphs
+	)
+}
+
+fn is_text(cell: Cell) -> bool { cell.c != ' ' && cell.c != '█' && Shape::of(cell.c).is_none() }
+
+/// Colours are written out in full, with no colour following the surrounding document's text colour
+fn hex(colour: Option<Colour>) -> String {
+	colour.map_or_else(|| String::from("currentColor"), Colour::hex)
+}
+
+fn attributes(style: Style) -> String {
+	let mut attributes = String::new();
+	if style.bold {
+		attributes.push_str(r#" font-weight="bold""#);
+	}
+	if style.dim {
+		attributes.push_str(r#" opacity="0.5""#);
+	}
+	attributes
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn row(text: &str) -> Vec<Cell> {
+		text.chars()
+			.map(|c| Cell {
+				c,
+				style: Style::default(),
+			})
+			.collect()
+	}
+
+	#[test]
+	fn joins_touching_segments_of_one_stroke() {
+		let red = Stroke::new("red", Weight::Light, false);
+		let blue = Stroke::new("blue", Weight::Light, false);
+		let mut runs = Runs::default();
+		runs.add(&red, 1, 0, 1);
+		runs.add(&red, 3, 0, 1);
+		runs.add(&red, 1, 1, 2);
+		runs.add(&blue, 1, 2, 3);
+		runs.add(&blue, 3, 3, 4);
+		runs.add(&blue, 3, 5, 6);
+
+		let runs = runs
+			.runs
+			.iter()
+			.map(|run| (run.stroke.colour.as_str(), run.fixed, run.start, run.end))
+			.collect::<Vec<_>>();
+		assert_eq!(
+			runs,
+			vec![
+				("red", 1, 0, 2),
+				("red", 3, 0, 1),
+				("blue", 1, 2, 3),
+				("blue", 3, 3, 4),
+				("blue", 3, 5, 6),
+			]
+		);
+	}
+
+	#[test]
+	fn draws_box_drawing_as_joined_lines() {
+		let svg = export(&[row("┌─┐"), row("└─┘")]);
+		assert!(svg.contains(
+			r#"<path d="M5 10H25M5 30H25M5 10V30M25 10V30" stroke="currentColor" stroke-width="1""#
+		));
+		assert!(!svg.contains("<text"));
+	}
+
+	#[test]
+	fn draws_double_lines_meeting_at_corners() {
+		let svg = export(&[row("╔═"), row("║")]);
+		// The outer lines meet at the corner's outside and the inner ones at its inside
+		assert!(svg.contains(r#"<path d="M3 8H20M7 12H20M3 8V40M7 12V40""#));
+	}
+
+	#[test]
+	fn draws_heavy_dashed_and_rounded_lines() {
+		let svg = export(&[row("━┅╮")]);
+		assert!(svg.contains(
+			r#"<path d="M0 10H10" stroke="currentColor" stroke-width="2" stroke-linecap"#
+		));
+		assert!(svg.contains(
+			r#"<path d="M10 10H20" stroke="currentColor" stroke-width="2" stroke-dasharray="2 3""#
+		));
+		assert!(
+			svg.contains(r#"<path d="M20 10Q25 10 25 20" stroke="currentColor" stroke-width="1""#)
+		);
+	}
+
+	#[test]
+	fn writes_escaped_text_runs_split_at_spaces() {
+		let svg = export(&[row("a<b&c> d")]);
+		assert!(svg.contains(
+			r#"x="0" y="15" textLength="60" fill="currentColor">a&lt;b&amp;c&gt;</text>"#
+		));
+		assert!(svg.contains(r#"x="70" y="15" textLength="10" fill="currentColor">d</text>"#));
+	}
+}
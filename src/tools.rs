@@ -1,6 +1,6 @@
 pub mod block;
+pub mod erase;
 
-mod erase;
 mod freehand;
 mod line;
 mod none;
@@ -10,30 +10,98 @@ mod thick_line;
 
 use crossterm::event::{KeyEvent, MouseEventKind};
 
-use crate::{buffer::Buffer, state::State};
+use serde::{Deserialize, Serialize};
+
+use crate::{box_drawing::LineStyle, buffer::Buffer, state::State, style::Style};
+
+/// The id and bounding box of a tool which the ends of lines can be anchored to
+pub type AnchorTarget = (u64, (isize, isize, isize, isize));
 
 pub trait Tool {
 	fn mouse_event(&mut self, x: isize, y: isize, kind: MouseEventKind) -> fn(state: &mut State);
 
 	fn key_event(&mut self, event: KeyEvent) -> fn(state: &mut State);
 
-	fn bounding_box(&self) -> Option<(usize, usize, usize, usize)>;
+	fn bounding_box(&self) -> Option<(isize, isize, isize, isize)>;
 
 	fn render(&self, buffer: &mut Buffer, ascii_mode: bool);
 
 	fn render_bounded(
 		&self,
-		min_x: usize,
-		max_x: usize,
-		min_y: usize,
-		max_y: usize,
+		min_x: isize,
+		max_x: isize,
+		min_y: isize,
+		max_y: isize,
 		buffer: &mut Buffer,
 		ascii_mode: bool,
 	);
 
+	fn translate(&mut self, x: isize, y: isize);
+
+	fn handles(&self) -> Vec<(isize, isize)>;
+
+	fn move_handle(&mut self, handle: usize, x: isize, y: isize);
+
+	fn set_style(&mut self, style: Style);
+
+	/// Only tools which are typed into take pasted text, while they are being drawn
+	fn paste(&mut self, _: &str) {}
+
+	/// Whether the select tool can pick the tool out, which it can't for tools with nothing to see
+	fn selectable(&self) -> bool { true }
+
+	/// Only tools which draw box drawing lines have a line style
+	fn set_line_style(&mut self, _: LineStyle) {}
+
+	/// Only rectangles can have the ends of lines anchored to them, and are given an id to be
+	/// referred to by
+	fn anchor_id(&self) -> Option<u64> { None }
+
+	fn set_anchor_id(&mut self, _: u64) {}
+
+	/// The ids of whatever the ends of a line are anchored to, whether or not it still exists
+	fn anchored_ids(&self) -> Vec<u64> { Vec::new() }
+
+	/// Drops anchors to anything other than the `ids`, as what they were anchored to is gone for good
+	fn keep_anchors(&mut self, _: &[u64]) {}
+
+	/// Anchors any ends of a finished line which lie on the border of one of the `targets`
+	fn attach(&mut self, _: &[AnchorTarget]) {}
+
+	/// Moves anchored ends to follow the bounding boxes of the `targets` they are anchored to
+	fn reroute(&mut self, _: &[AnchorTarget]) {}
+
+	fn save(&self) -> Option<SavedTool>;
+
 	fn complete(&self) -> bool;
 }
 
+#[derive(Serialize, Deserialize)]
+#[serde(tag = "tool", rename_all = "snake_case")]
+pub enum SavedTool {
+	Freehand(freehand::Freehand),
+	Erase(erase::Erase),
+	Rectangle(rectangle::Rectangle),
+	Text(text::Text),
+	Line(line::Line),
+	ThickLine(thick_line::ThickLine),
+	Block(block::Block),
+}
+
+impl SavedTool {
+	pub fn into_tool(self) -> Box<dyn Tool> {
+		match self {
+			SavedTool::Freehand(tool) => Box::new(tool),
+			SavedTool::Erase(tool) => Box::new(tool),
+			SavedTool::Rectangle(tool) => Box::new(tool),
+			SavedTool::Text(tool) => Box::new(tool),
+			SavedTool::Line(tool) => Box::new(tool),
+			SavedTool::ThickLine(tool) => Box::new(tool),
+			SavedTool::Block(tool) => Box::new(tool),
+		}
+	}
+}
+
 #[derive(Clone, Copy)]
 pub enum ToolSelect {
 	None,
@@ -42,20 +110,32 @@ pub enum ToolSelect {
 	Rectangle,
 	Text,
 	Line,
+	Elbow,
+	Polyline,
+	Diagonal,
 	ThickLine,
+	Select,
 }
 
 impl ToolSelect {
-	pub fn to_tool(&self) -> Box<dyn Tool> {
-		match self {
-			ToolSelect::None => Box::new(none::None::default()),
+	pub fn to_tool(self, style: Style, line_style: LineStyle) -> Box<dyn Tool> {
+		let mut tool: Box<dyn Tool> = match self {
+			ToolSelect::None => Box::new(none::None),
 			ToolSelect::Freehand => Box::new(freehand::Freehand::default()),
 			ToolSelect::Erase => Box::new(erase::Erase::default()),
 			ToolSelect::Rectangle => Box::new(rectangle::Rectangle::default()),
 			ToolSelect::Text => Box::new(text::Text::default()),
 			ToolSelect::Line => Box::new(line::Line::default()),
+			ToolSelect::Elbow => Box::new(line::Line::new(line::LineMode::Elbow)),
+			ToolSelect::Polyline => Box::new(line::Line::new(line::LineMode::Polyline)),
+			ToolSelect::Diagonal => Box::new(line::Line::new(line::LineMode::Diagonal)),
 			ToolSelect::ThickLine => Box::new(thick_line::ThickLine::default()),
-		}
+			// Selection is handled by the workspace itself
+			ToolSelect::Select => Box::new(none::None),
+		};
+		tool.set_style(style);
+		tool.set_line_style(line_style);
+		tool
 	}
 
 	pub fn name(&self) -> &'static str {
@@ -66,7 +146,35 @@ impl ToolSelect {
 			ToolSelect::Rectangle => "Rectangle",
 			ToolSelect::Text => "Text",
 			ToolSelect::Line => "Line",
+			ToolSelect::Elbow => "Elbow Line",
+			ToolSelect::Polyline => "Polyline",
+			ToolSelect::Diagonal => "Diagonal Line",
 			ToolSelect::ThickLine => "Thick Line",
+			ToolSelect::Select => "Select",
 		}
 	}
 }
+
+pub fn merge_bounding_boxes(
+	a: Option<(isize, isize, isize, isize)>,
+	b: Option<(isize, isize, isize, isize)>,
+) -> Option<(isize, isize, isize, isize)> {
+	match (a, b) {
+		(
+			Some((min_x, max_x, min_y, max_y)),
+			Some((new_min_x, new_max_x, new_min_y, new_max_y)),
+		) => Some((
+			min_x.min(new_min_x),
+			max_x.max(new_max_x),
+			min_y.min(new_min_y),
+			max_y.max(new_max_y),
+		)),
+		(Some(old), None) => Some(old),
+		(None, Some(new)) => Some(new),
+		(None, None) => None,
+	}
+}
+
+fn translate_point((x, y): (isize, isize), offset_x: isize, offset_y: isize) -> (isize, isize) {
+	(x + offset_x, y + offset_y)
+}
@@ -1,24 +1,96 @@
 use crossterm::event::{KeyEvent, MouseEventKind};
 
-use std::{fs::read_to_string, path::Path};
+use serde::{Deserialize, Serialize};
 
-use crate::{buffer::Buffer, error::Result, state::State, tools::Tool};
+use std::{collections::HashMap, fs::read_to_string, path::Path};
 
-#[derive(Default)]
+use crate::{
+	ansi::{self, ANSI_EXTENSION},
+	box_drawing::BoxFlags,
+	buffer::{Buffer, Cell},
+	error::Result,
+	state::State,
+	style::Style,
+	tools::{translate_point, SavedTool, Tool},
+};
+
+#[derive(Default, Clone, Serialize, Deserialize)]
 pub struct Block {
-	chars: Vec<(usize, usize, char)>,
+	chars: Vec<(isize, isize, char)>,
+	/// The style of each char, left empty when nothing is styled
+	#[serde(default, skip_serializing_if = "Vec::is_empty")]
+	styles: Vec<Style>,
 }
 
 impl Block {
+	/// Reads a plain text file, or keeps the colours of an ansi file
 	pub fn new(input_file: &Path) -> Result<Self> {
-		let file_str = read_to_string(input_file)?;
-		let chars = file_str
+		let text = read_to_string(input_file)?;
+		if input_file.extension().and_then(|e| e.to_str()) == Some(ANSI_EXTENSION) {
+			Ok(Self::from_cells(ansi::import(&text)))
+		}
+		else {
+			Ok(Self::from_text(&text))
+		}
+	}
+
+	pub fn from_text(text: &str) -> Self {
+		let chars = text
 			.lines()
 			.enumerate()
-			.flat_map(|(y, line)| line.chars().enumerate().map(move |(x, c)| (x, y, c)))
+			.flat_map(|(y, line)| {
+				line.chars()
+					.enumerate()
+					.map(move |(x, c)| (x as isize, y as isize, c))
+			})
 			.filter(|(_, _, c)| *c != ' ')
 			.collect::<Vec<_>>();
-		Ok(Self { chars })
+		Self {
+			chars,
+			styles: Vec::new(),
+		}
+	}
+
+	pub fn from_cells(cells: Vec<(isize, isize, Cell)>) -> Self {
+		let (chars, styles) = cells
+			.into_iter()
+			.map(|(x, y, cell)| ((x, y, cell.c), cell.style))
+			.unzip();
+		Self { chars, styles }
+	}
+
+	/// Replaces ascii box drawing characters with their unicode equivalents
+	pub fn infer_box_drawing(&mut self) {
+		let lookup = self
+			.chars
+			.iter()
+			.map(|(x, y, c)| ((*x, *y), *c))
+			.collect::<HashMap<_, _>>();
+		let get = |x: isize, y: isize| lookup.get(&(x, y)).copied().unwrap_or(' ');
+
+		for (x, y, c) in &mut self.chars {
+			let flags = BoxFlags::from_ascii_neighbours(
+				*c,
+				get(*x, *y - 1),
+				get(*x, *y + 1),
+				get(*x - 1, *y),
+				get(*x + 1, *y),
+			);
+			if flags != BoxFlags::NONE {
+				*c = flags.to_char(false);
+			}
+		}
+	}
+
+	fn style(&self, index: usize) -> Style { self.styles.get(index).copied().unwrap_or_default() }
+
+	fn glyph(c: char, ascii_mode: bool) -> char {
+		if ascii_mode {
+			BoxFlags::ascii_glyph(c)
+		}
+		else {
+			c
+		}
 	}
 }
 
@@ -29,7 +101,7 @@ impl Tool for Block {
 
 	fn key_event(&mut self, _: KeyEvent) -> fn(state: &mut State) { |_| () }
 
-	fn bounding_box(&self) -> Option<(usize, usize, usize, usize)> {
+	fn bounding_box(&self) -> Option<(isize, isize, isize, isize)> {
 		self.chars
 			.iter()
 			.copied()
@@ -41,28 +113,50 @@ impl Tool for Block {
 			})
 	}
 
-	fn render(&self, buffer: &mut Buffer, _: bool) {
+	fn render(&self, buffer: &mut Buffer, ascii_mode: bool) {
 		self.chars
 			.iter()
 			.copied()
-			.for_each(|(x, y, c)| buffer.render_point(x, y, c))
+			.enumerate()
+			.for_each(|(index, (x, y, c))| {
+				buffer.render_point(x, y, Self::glyph(c, ascii_mode), self.style(index))
+			})
 	}
 
 	fn render_bounded(
 		&self,
-		min_x: usize,
-		max_x: usize,
-		min_y: usize,
-		max_y: usize,
+		min_x: isize,
+		max_x: isize,
+		min_y: isize,
+		max_y: isize,
 		buffer: &mut Buffer,
-		_: bool,
+		ascii_mode: bool,
 	) {
 		self.chars
 			.iter()
 			.copied()
-			.filter(|(x, y, _)| (min_x <= *x && *x < max_x) && (min_y <= *y && *y < max_y))
-			.for_each(|(x, y, c)| buffer.render_point(x, y, c))
+			.enumerate()
+			.filter(|(_, (x, y, _))| (min_x <= *x && *x < max_x) && (min_y <= *y && *y < max_y))
+			.for_each(|(index, (x, y, c))| {
+				buffer.render_point(x, y, Self::glyph(c, ascii_mode), self.style(index))
+			})
 	}
 
+	fn translate(&mut self, x: isize, y: isize) {
+		self.chars.iter_mut().for_each(|(char_x, char_y, _)| {
+			let (new_x, new_y) = translate_point((*char_x, *char_y), x, y);
+			*char_x = new_x;
+			*char_y = new_y;
+		});
+	}
+
+	fn handles(&self) -> Vec<(isize, isize)> { Vec::new() }
+
+	fn move_handle(&mut self, _: usize, _: isize, _: isize) {}
+
+	fn set_style(&mut self, style: Style) { self.styles = vec![style; self.chars.len()]; }
+
+	fn save(&self) -> Option<SavedTool> { Some(SavedTool::Block(self.clone())) }
+
 	fn complete(&self) -> bool { true }
 }
@@ -2,31 +2,32 @@ use crossterm::event::{KeyEvent, MouseEventKind};
 
 use line_drawing::Bresenham;
 
-use std::convert::TryFrom;
+use serde::{Deserialize, Serialize};
 
-use crate::{buffer::Buffer, state::State, tools::Tool};
+use crate::{
+	buffer::Buffer,
+	state::State,
+	style::Style,
+	tools::{translate_point, SavedTool, Tool},
+};
 
-#[derive(Default)]
+#[derive(Default, Clone, Serialize, Deserialize)]
 pub struct Freehand {
 	started: bool,
-	points: Vec<(usize, usize)>,
+	points: Vec<(isize, isize)>,
+	#[serde(default)]
+	style: Style,
 }
 
 impl Tool for Freehand {
 	fn mouse_event(&mut self, x: isize, y: isize, kind: MouseEventKind) -> fn(state: &mut State) {
-		if let (Ok(x), Ok(y)) = (usize::try_from(x), usize::try_from(y)) {
-			if let Some((old_x, old_y)) = self.points.last() {
-				for (x, y) in
-					Bresenham::new((*old_x as isize, *old_y as isize), (x as isize, y as isize))
-						.skip(1)
-				{
-					self.points.push((x as usize, y as usize));
-				}
-			}
-			else {
-				self.started = true;
-				self.points.push((x, y));
-			}
+		if let Some(last) = self.points.last() {
+			let line = Bresenham::new(*last, (x, y)).skip(1).collect::<Vec<_>>();
+			self.points.extend(line);
+		}
+		else {
+			self.started = true;
+			self.points.push((x, y));
 		}
 
 		// Finish tool when mouse releases
@@ -39,7 +40,7 @@ impl Tool for Freehand {
 
 	fn key_event(&mut self, _: KeyEvent) -> fn(state: &mut State) { |_| () }
 
-	fn bounding_box(&self) -> Option<(usize, usize, usize, usize)> {
+	fn bounding_box(&self) -> Option<(isize, isize, isize, isize)> {
 		self.points
 			.iter()
 			.copied()
@@ -56,15 +57,15 @@ impl Tool for Freehand {
 			.iter()
 			.copied()
 			.map(|(x, y)| (x, y, if ascii_mode { '#' } else { '█' }))
-			.for_each(|(x, y, c)| buffer.render_point(x, y, c))
+			.for_each(|(x, y, c)| buffer.render_point(x, y, c, self.style))
 	}
 
 	fn render_bounded(
 		&self,
-		min_x: usize,
-		max_x: usize,
-		min_y: usize,
-		max_y: usize,
+		min_x: isize,
+		max_x: isize,
+		min_y: isize,
+		max_y: isize,
 		buffer: &mut Buffer,
 		ascii_mode: bool,
 	) {
@@ -73,8 +74,22 @@ impl Tool for Freehand {
 			.copied()
 			.filter(|(x, y)| (min_x <= *x && *x < max_x) && (min_y <= *y && *y < max_y))
 			.map(|(x, y)| (x, y, if ascii_mode { '#' } else { '█' }))
-			.for_each(|(x, y, c)| buffer.render_point(x, y, c))
+			.for_each(|(x, y, c)| buffer.render_point(x, y, c, self.style))
+	}
+
+	fn translate(&mut self, x: isize, y: isize) {
+		self.points
+			.iter_mut()
+			.for_each(|point| *point = translate_point(*point, x, y));
 	}
 
+	fn handles(&self) -> Vec<(isize, isize)> { Vec::new() }
+
+	fn move_handle(&mut self, _: usize, _: isize, _: isize) {}
+
+	fn set_style(&mut self, style: Style) { self.style = style; }
+
+	fn save(&self) -> Option<SavedTool> { Some(SavedTool::Freehand(self.clone())) }
+
 	fn complete(&self) -> bool { self.started }
 }
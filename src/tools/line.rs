@@ -1,64 +1,519 @@
-use crossterm::event::{KeyEvent, MouseEventKind};
+use crossterm::event::{KeyCode, KeyEvent, MouseEventKind};
 
-use std::convert::TryFrom;
+use line_drawing::Bresenham;
 
-use crate::{box_drawing::BoxFlags, buffer::Buffer, state::State, tools::Tool};
+use serde::{Deserialize, Serialize};
 
-#[derive(Default)]
+use std::collections::{hash_map::Entry, HashMap};
+
+use crate::{
+	box_drawing::{BoxFlags, LineStyle},
+	buffer::Buffer,
+	state::State,
+	style::Style,
+	tools::{translate_point, AnchorTarget, SavedTool, Tool},
+};
+
+#[derive(Default, Clone, Serialize, Deserialize)]
 pub struct Line {
 	started: bool,
-	start: (usize, usize),
-	end: (usize, usize),
+	start: (isize, isize),
+	end: (isize, isize),
 	complete: bool,
+	#[serde(default)]
+	style: Style,
+	#[serde(default)]
+	line_style: LineStyle,
+	#[serde(default)]
+	start_marker: Marker,
+	#[serde(default)]
+	end_marker: Marker,
+	#[serde(default)]
+	mode: LineMode,
+	/// Elbows turn from vertical to horizontal rather than the other way around
+	#[serde(default)]
+	vertical_first: bool,
+	/// The points clicked between the start and end of a polyline
+	#[serde(default, skip_serializing_if = "Vec::is_empty")]
+	waypoints: Vec<(isize, isize)>,
+	/// Where the mouse is while a polyline is being drawn, previewing the next run without being
+	/// part of the line
+	#[serde(skip)]
+	hover: Option<(isize, isize)>,
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	start_anchor: Option<Anchor>,
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	end_anchor: Option<Anchor>,
+}
+
+/// A side of a rectangle
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+enum Side {
+	Top,
+	Bottom,
+	Left,
+	Right,
+}
+
+/// Where an end of a line is attached to a rectangle, kept as a reference to the rectangle so
+/// that the line follows it when it moves or is resized
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Serialize, Deserialize)]
+struct Anchor {
+	id: u64,
+	side: Side,
+	/// How far along the side from its top or left corner
+	offset: isize,
+}
+
+impl Anchor {
+	/// The anchor for a point on the border of one of the `targets`, preferring those on top
+	fn find((x, y): (isize, isize), targets: &[AnchorTarget]) -> Option<Self> {
+		targets
+			.iter()
+			.rev()
+			.find_map(|&(id, (min_x, max_x, min_y, max_y))| {
+				let within_x = min_x <= x && x <= max_x;
+				let within_y = min_y <= y && y <= max_y;
+				let (side, offset) = match () {
+					_ if within_x && y == min_y => (Side::Top, x - min_x),
+					_ if within_x && y == max_y => (Side::Bottom, x - min_x),
+					_ if within_y && x == min_x => (Side::Left, y - min_y),
+					_ if within_y && x == max_x => (Side::Right, y - min_y),
+					_ => return None,
+				};
+				Some(Self { id, side, offset })
+			})
+	}
+
+	/// The point on a rectangle's border, keeping to the side when it has shrunk
+	fn point(&self, (min_x, max_x, min_y, max_y): (isize, isize, isize, isize)) -> (isize, isize) {
+		let along_x = min_x + self.offset.min(max_x - min_x);
+		let along_y = min_y + self.offset.min(max_y - min_y);
+		match self.side {
+			Side::Top => (along_x, min_y),
+			Side::Bottom => (along_x, max_y),
+			Side::Left => (min_x, along_y),
+			Side::Right => (max_x, along_y),
+		}
+	}
+
+	/// Whether a line leaves the side at a right angle by going vertically
+	fn vertical(&self) -> bool { matches!(self.side, Side::Top | Side::Bottom) }
+}
+
+/// How a line gets from its start to its end
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LineMode {
+	/// Horizontal or vertical, whichever is longer
+	#[default]
+	Straight,
+	/// Horizontal then vertical, or the reverse, with a corner between
+	Elbow,
+	/// A straight run to each waypoint in turn
+	Polyline,
+	/// Straight from the start to the end at any angle, using slashes for the diagonal steps
+	Diagonal,
+}
+
+/// What is drawn in one cell of a line
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+enum Stroke {
+	Box(BoxFlags),
+	/// Going up to the right, `╱`
+	Rising,
+	/// Going down to the right, `╲`
+	Falling,
+}
+
+impl Stroke {
+	/// Draws over a character already on the canvas, crossing any slash going the other way
+	fn draw(self, existing: char, style: LineStyle, ascii_mode: bool) -> char {
+		let (rising, falling, cross) = match ascii_mode {
+			false => ('╱', '╲', '╳'),
+			true => ('/', '\\', 'X'),
+		};
+		match self {
+			Stroke::Box(flags) => flags.draw(existing, style, ascii_mode),
+			Stroke::Rising if existing == falling || existing == cross => cross,
+			Stroke::Falling if existing == rising || existing == cross => cross,
+			Stroke::Rising => rising,
+			Stroke::Falling => falling,
+		}
+	}
+}
+
+/// What is drawn at an end of a line
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Marker {
+	#[default]
+	None,
+	Arrow,
+	Dot,
+	Diamond,
+}
+
+impl Marker {
+	fn next(self) -> Self {
+		match self {
+			Marker::None => Marker::Arrow,
+			Marker::Arrow => Marker::Dot,
+			Marker::Dot => Marker::Diamond,
+			Marker::Diamond => Marker::None,
+		}
+	}
+
+	/// The character for a marker at an end of a line which points in `direction`
+	fn to_char(self, direction: BoxFlags, ascii_mode: bool) -> Option<char> {
+		let arrow = |unicode: [char; 4], ascii: [char; 4]| {
+			let arrows = if ascii_mode { ascii } else { unicode };
+			match direction {
+				BoxFlags::UP => arrows[0],
+				BoxFlags::DOWN => arrows[1],
+				BoxFlags::LEFT => arrows[2],
+				_ => arrows[3],
+			}
+		};
+		match (self, ascii_mode) {
+			(Marker::None, _) => None,
+			(Marker::Arrow, _) => Some(arrow(['▲', '▼', '◀', '▶'], ['^', 'v', '<', '>'])),
+			(Marker::Dot, false) => Some('●'),
+			(Marker::Dot, true) => Some('o'),
+			(Marker::Diamond, false) => Some('◆'),
+			(Marker::Diamond, true) => Some('*'),
+		}
+	}
+}
+
+/// The point reached from `from` by going towards `to` along whichever axis is further
+fn snap((from_x, from_y): (isize, isize), (to_x, to_y): (isize, isize)) -> (isize, isize) {
+	if (to_x - from_x).abs() >= (to_y - from_y).abs() {
+		(to_x, from_y)
+	}
+	else {
+		(from_x, to_y)
+	}
+}
+
+/// The directions pointing forwards and backwards along a horizontal or vertical run
+fn run_directions(
+	(from_x, from_y): (isize, isize),
+	(to_x, to_y): (isize, isize),
+) -> Option<(BoxFlags, BoxFlags)> {
+	match ((to_x - from_x).signum(), (to_y - from_y).signum()) {
+		(1, _) => Some((BoxFlags::RIGHT, BoxFlags::LEFT)),
+		(-1, _) => Some((BoxFlags::LEFT, BoxFlags::RIGHT)),
+		(_, 1) => Some((BoxFlags::DOWN, BoxFlags::UP)),
+		(_, -1) => Some((BoxFlags::UP, BoxFlags::DOWN)),
+		_ => None,
+	}
+}
+
+impl Line {
+	pub fn new(mode: LineMode) -> Self {
+		Self {
+			mode,
+			..Self::default()
+		}
+	}
+
+	fn anchored(&self) -> bool { self.start_anchor.is_some() || self.end_anchor.is_some() }
+
+	/// Lines each waypoint up with the one before it, so that the waypoints are the corners drawn
+	fn snap_waypoints(&mut self) {
+		let mut last = self.start;
+		for waypoint in &mut self.waypoints {
+			*waypoint = snap(last, *waypoint);
+			last = *waypoint;
+		}
+	}
+
+	/// The corners of the line, joined by horizontal and vertical runs
+	fn path(&self) -> Vec<(isize, isize)> {
+		let (start_x, start_y) = self.start;
+		let (end_x, end_y) = self.end;
+
+		match self.mode {
+			// Anchored lines have to bend to stay attached when what they are anchored to moves
+			LineMode::Straight if !self.anchored() => vec![self.start, snap(self.start, self.end)],
+			LineMode::Straight | LineMode::Elbow => {
+				let corner = if self.vertical_first {
+					(start_x, end_y)
+				}
+				else {
+					(end_x, start_y)
+				};
+				vec![self.start, corner, self.end]
+			}
+			LineMode::Polyline => {
+				let mut path = vec![self.start];
+				for point in self
+					.waypoints
+					.iter()
+					.chain(Some(&self.end))
+					.chain(&self.hover)
+				{
+					let last = path[path.len() - 1];
+					path.push(snap(last, *point));
+				}
+				path
+			}
+			LineMode::Diagonal => vec![self.start, self.end],
+		}
+	}
+
+	/// Each cell the line passes through, with what is drawn there
+	fn cells(&self) -> Vec<(isize, isize, Stroke)> {
+		if self.mode == LineMode::Diagonal {
+			return self.diagonal_cells();
+		}
+
+		self.box_cells()
+			.into_iter()
+			.map(|(x, y, flags)| (x, y, Stroke::Box(flags)))
+			.collect()
+	}
+
+	/// Each cell of a line made of horizontal and vertical runs, with the directions it leaves
+	/// the cell in
+	fn box_cells(&self) -> Vec<(isize, isize, BoxFlags)> {
+		let mut cells = Vec::<(isize, isize, BoxFlags)>::new();
+		// Where each cell is in the list, for cells the line passes through more than once
+		let mut indices = HashMap::<(isize, isize), usize>::new();
+		let mut add = |x: isize, y: isize, flags: BoxFlags| match indices.entry((x, y)) {
+			Entry::Occupied(index) => cells[*index.get()].2 |= flags,
+			Entry::Vacant(index) => {
+				index.insert(cells.len());
+				cells.push((x, y, flags));
+			}
+		};
+
+		for run in self.path().windows(2) {
+			let (from, to) = (run[0], run[1]);
+			let (forwards, backwards) = match run_directions(from, to) {
+				Some(directions) => directions,
+				None => continue,
+			};
+			let (step_x, step_y) = ((to.0 - from.0).signum(), (to.1 - from.1).signum());
+			let length = (to.0 - from.0).abs().max((to.1 - from.1).abs());
+			for step in 0..=length {
+				let mut flags = BoxFlags::NONE;
+				flags.set(backwards, step > 0);
+				flags.set(forwards, step < length);
+				add(from.0 + step_x * step, from.1 + step_y * step, flags);
+			}
+		}
+
+		// A line with no length is still drawn as a stub
+		if cells.is_empty() {
+			cells.push((self.start.0, self.start.1, BoxFlags::RIGHT));
+		}
+		cells
+	}
+
+	/// Each cell of a diagonal line. Every diagonal step is drawn as a slash in the cell it leaves,
+	/// and the cell it arrives at carries on with a box line reaching back towards the slash so that
+	/// the two join up
+	fn diagonal_cells(&self) -> Vec<(isize, isize, Stroke)> {
+		let points = Bresenham::new(self.start, self.end).collect::<Vec<_>>();
+		if points.len() < 2 {
+			return vec![(self.start.0, self.start.1, Stroke::Box(BoxFlags::RIGHT))];
+		}
+
+		let diagonal = |(dx, dy): (isize, isize)| dx != 0 && dy != 0;
+		let slash = |(dx, dy): (isize, isize)| match dx == dy {
+			true => Stroke::Falling,
+			false => Stroke::Rising,
+		};
+		let arm = |(dx, dy): (isize, isize), horizontal: bool| match (horizontal, dx, dy) {
+			(true, dx, _) if dx < 0 => BoxFlags::LEFT,
+			(true, ..) => BoxFlags::RIGHT,
+			(false, _, dy) if dy < 0 => BoxFlags::UP,
+			(false, ..) => BoxFlags::DOWN,
+		};
+
+		points
+			.iter()
+			.enumerate()
+			.map(|(index, &(x, y))| {
+				let step = |(to_x, to_y): (isize, isize)| (to_x - x, to_y - y);
+				let previous = index.checked_sub(1).map(|previous| step(points[previous]));
+				let next = points.get(index + 1).map(|next| step(*next));
+				let stroke = match (previous, next) {
+					(_, Some(next)) if diagonal(next) => slash(next),
+					(Some(previous), None) if diagonal(previous) => slash(previous),
+					(Some(previous), Some(next)) if diagonal(previous) => {
+						Stroke::Box(arm(previous, next.1 == 0) | arm(next, next.1 == 0))
+					}
+					(previous, next) => Stroke::Box(
+						previous
+							.into_iter()
+							.chain(next)
+							.fold(BoxFlags::NONE, |flags, step| flags | arm(step, step.1 == 0)),
+					),
+				};
+				(x, y, stroke)
+			})
+			.collect()
+	}
+
+	/// The cells at each end of the line, along with the direction each end points in
+	fn ends(&self) -> [((isize, isize), BoxFlags); 2] {
+		let path = self.path();
+		let runs = path
+			.windows(2)
+			.filter_map(|run| run_directions(run[0], snap(run[0], run[1])))
+			.collect::<Vec<_>>();
+		let start_direction = runs
+			.first()
+			.map(|(_, backwards)| *backwards)
+			.unwrap_or(BoxFlags::LEFT);
+		let end_direction = runs
+			.last()
+			.map(|(forwards, _)| *forwards)
+			.unwrap_or(BoxFlags::RIGHT);
+		[
+			(path[0], start_direction),
+			(path[path.len() - 1], end_direction),
+		]
+	}
+
+	/// The marker drawn at a point, if it is an end of the line with one
+	fn marker(&self, point: (isize, isize), ascii_mode: bool) -> Option<char> {
+		let [(start, start_direction), (end, end_direction)] = self.ends();
+		// The end marker wins when both ends are in the same cell
+		let end_marker = (point == end)
+			.then(|| self.end_marker.to_char(end_direction, ascii_mode))
+			.flatten();
+		let start_marker = (point == start)
+			.then(|| self.start_marker.to_char(start_direction, ascii_mode))
+			.flatten();
+		end_marker.or(start_marker)
+	}
+
+	fn render_cell(
+		&self,
+		x: isize,
+		y: isize,
+		stroke: Stroke,
+		buffer: &mut Buffer,
+		ascii_mode: bool,
+	) {
+		let existing = buffer.get_point(x, y);
+		let c = match self.marker((x, y), ascii_mode) {
+			Some(marker) => marker,
+			// Slashes ending on a box line leave it be, meeting it rather than breaking it
+			None if !matches!(stroke, Stroke::Box(_))
+				&& [self.start, self.end].contains(&(x, y))
+				&& BoxFlags::from_char(existing, ascii_mode) != BoxFlags::NONE =>
+			{
+				return
+			}
+			None => stroke.draw(existing, self.line_style, ascii_mode),
+		};
+		buffer.render_point(x, y, c, self.style)
+	}
 }
 
 impl Tool for Line {
 	fn mouse_event(&mut self, x: isize, y: isize, kind: MouseEventKind) -> fn(state: &mut State) {
 		match kind {
 			MouseEventKind::Down(_) => {
-				if let (Ok(x), Ok(y)) = (usize::try_from(x), usize::try_from(y)) {
-					if !self.started {
-						self.start = (x, y);
-						self.end = (x, y);
-						self.started = true;
-						|_| ()
-					}
-					else {
-						// Edge case - dragged off edge then released mouse
-						self.end = (x, y);
+				if !self.started {
+					self.start = (x, y);
+					self.end = (x, y);
+					self.started = true;
+				}
+				else if self.mode == LineMode::Polyline {
+					// Each further click ends the line there, turning where it ended before
+					let end = snap(self.end, (x, y));
+					if end != self.end {
+						if self.complete {
+							self.waypoints.push(self.end);
+						}
+						self.end = end;
 						self.complete = true;
-						|_| ()
 					}
 				}
 				else {
-					|_| ()
-				}
-			}
-			MouseEventKind::Drag(_) => {
-				if let (Ok(x), Ok(y)) = (usize::try_from(x), usize::try_from(y)) {
+					// Edge case - dragged off edge then released mouse
 					self.end = (x, y);
 					self.complete = true;
 				}
 				|_| ()
 			}
+			// Polylines follow the mouse between clicks
+			MouseEventKind::Moved | MouseEventKind::Drag(_)
+				if self.started && self.mode == LineMode::Polyline =>
+			{
+				self.hover = Some((x, y));
+				|_| ()
+			}
+			MouseEventKind::Drag(_) => {
+				self.end = (x, y);
+				self.complete = true;
+				|_| ()
+			}
+			// Polylines keep going until they are finished from the keyboard
+			MouseEventKind::Up(_) if self.mode == LineMode::Polyline => |_| (),
 			MouseEventKind::Up(_) => |state| state.reset_current_mouse_element(),
 
 			_ => |_| (),
 		}
 	}
 
-	fn key_event(&mut self, _: KeyEvent) -> fn(state: &mut State) { |_| () }
+	fn key_event(&mut self, event: KeyEvent) -> fn(state: &mut State) {
+		// Markers and elbows are picked while the line is being drawn
+		match event {
+			KeyEvent {
+				code: KeyCode::Char('<'),
+				..
+			} => self.start_marker = self.start_marker.next(),
+			KeyEvent {
+				code: KeyCode::Char('>'),
+				..
+			} => self.end_marker = self.end_marker.next(),
+			KeyEvent {
+				code: KeyCode::Char(' '),
+				..
+			} => self.vertical_first = !self.vertical_first,
+			// A line which hasn't gone anywhere yet keeps being drawn
+			KeyEvent {
+				code: KeyCode::Enter,
+				..
+			} if self.started && !self.complete && self.end == self.start => (),
+			KeyEvent {
+				code: KeyCode::Enter,
+				..
+			} => {
+				// Polylines end at the last click, wherever the mouse has gone since
+				self.hover = None;
+				self.complete = self.started;
+				return |state| state.reset_current_mouse_element();
+			}
+			// Incomplete tools are thrown away when the next one starts
+			KeyEvent {
+				code: KeyCode::Esc, ..
+			} => {
+				self.hover = None;
+				self.complete = false;
+				return |state| state.reset_current_mouse_element();
+			}
+			_ => (),
+		}
+		|_| ()
+	}
 
-	fn bounding_box(&self) -> Option<(usize, usize, usize, usize)> {
+	fn bounding_box(&self) -> Option<(isize, isize, isize, isize)> {
 		if self.started {
-			let (start_x, start_y) = self.start;
-			let (end_x, end_y) = self.end;
-			Some((
-				start_x.min(end_x),
-				start_x.max(end_x),
-				start_y.min(end_y),
-				start_y.max(end_y),
-			))
+			self.path().into_iter().fold(None, |acc, (x, y)| match acc {
+				Some((min_x, max_x, min_y, max_y)) => {
+					Some((min_x.min(x), max_x.max(x), min_y.min(y), max_y.max(y)))
+				}
+				None => Some((x, x, y, y)),
+			})
 		}
 		else {
 			None
@@ -70,69 +525,17 @@ impl Tool for Line {
 			return;
 		}
 
-		let (start_x, start_y) = self.start;
-
-		let rect_min_x = self.start.0.min(self.end.0);
-		let rect_max_x = self.start.0.max(self.end.0);
-		let rect_min_y = self.start.1.min(self.end.1);
-		let rect_max_y = self.start.1.max(self.end.1);
-
-		if rect_max_x - rect_min_x >= rect_max_y - rect_min_y {
-			// Render horizontal line
-			(rect_min_x..=rect_max_x)
-				.map(|x| {
-					(
-						x,
-						start_y,
-						if x == rect_min_x {
-							BoxFlags::RIGHT
-						}
-						else if x == rect_max_x {
-							BoxFlags::LEFT
-						}
-						else {
-							BoxFlags::RIGHT | BoxFlags::LEFT
-						},
-					)
-				})
-				.for_each(|(x, y, box_dir)| {
-					let current_box = BoxFlags::from_char(buffer.get_point(x, y), ascii_mode);
-					let final_box = box_dir | current_box;
-					buffer.render_point(x, y, final_box.to_char(ascii_mode))
-				})
-		}
-		else {
-			// Render vertical line
-			(rect_min_y..=rect_max_y)
-				.map(|y| {
-					(
-						start_x,
-						y,
-						if y == rect_min_y {
-							BoxFlags::DOWN
-						}
-						else if y == rect_max_y {
-							BoxFlags::UP
-						}
-						else {
-							BoxFlags::UP | BoxFlags::DOWN
-						},
-					)
-				})
-				.for_each(|(x, y, box_dir)| {
-					let current_box = BoxFlags::from_char(buffer.get_point(x, y), ascii_mode);
-					let final_box = box_dir | current_box;
-					buffer.render_point(x, y, final_box.to_char(ascii_mode))
-				})
-		}
+		self.cells()
+			.into_iter()
+			.for_each(|(x, y, stroke)| self.render_cell(x, y, stroke, buffer, ascii_mode))
 	}
 
 	fn render_bounded(
 		&self,
-		min_x: usize,
-		max_x: usize,
-		min_y: usize,
-		max_y: usize,
+		min_x: isize,
+		max_x: isize,
+		min_y: isize,
+		max_y: isize,
 		buffer: &mut Buffer,
 		ascii_mode: bool,
 	) {
@@ -140,64 +543,117 @@ impl Tool for Line {
 			return;
 		}
 
-		let (start_x, start_y) = self.start;
+		self.cells()
+			.into_iter()
+			.filter(|(x, y, _)| (min_x <= *x && *x < max_x) && (min_y <= *y && *y < max_y))
+			.for_each(|(x, y, stroke)| self.render_cell(x, y, stroke, buffer, ascii_mode))
+	}
 
-		let rect_min_x = self.start.0.min(self.end.0);
-		let rect_max_x = self.start.0.max(self.end.0);
-		let rect_min_y = self.start.1.min(self.end.1);
-		let rect_max_y = self.start.1.max(self.end.1);
-
-		if rect_max_x - rect_min_x >= rect_max_y - rect_min_y {
-			// Render horizontal line
-			(rect_min_x..=rect_max_x)
-				.map(|x| {
-					(
-						x,
-						start_y,
-						if x == rect_min_x {
-							BoxFlags::RIGHT
-						}
-						else if x == rect_max_x {
-							BoxFlags::LEFT
-						}
-						else {
-							BoxFlags::RIGHT | BoxFlags::LEFT
-						},
-					)
-				})
-				.filter(|(x, y, _)| (min_x <= *x && *x < max_x) && (min_y <= *y && *y < max_y))
-				.for_each(|(x, y, box_dir)| {
-					let current_box = BoxFlags::from_char(buffer.get_point(x, y), ascii_mode);
-					let final_box = box_dir | current_box;
-					buffer.render_point(x, y, final_box.to_char(ascii_mode))
-				})
+	fn translate(&mut self, x: isize, y: isize) {
+		// Moving the whole line detaches it, until it is dropped onto another border
+		self.start_anchor = None;
+		self.end_anchor = None;
+		self.start = translate_point(self.start, x, y);
+		self.end = translate_point(self.end, x, y);
+		for waypoint in &mut self.waypoints {
+			*waypoint = translate_point(*waypoint, x, y);
 		}
-		else {
-			// Render vertical line
-			(rect_min_y..=rect_max_y)
-				.map(|y| {
-					(
-						start_x,
-						y,
-						if y == rect_min_y {
-							BoxFlags::DOWN
-						}
-						else if y == rect_max_y {
-							BoxFlags::UP
-						}
-						else {
-							BoxFlags::UP | BoxFlags::DOWN
-						},
-					)
-				})
-				.filter(|(x, y, _)| (min_x <= *x && *x < max_x) && (min_y <= *y && *y < max_y))
-				.for_each(|(x, y, box_dir)| {
-					let current_box = BoxFlags::from_char(buffer.get_point(x, y), ascii_mode);
-					let final_box = box_dir | current_box;
-					buffer.render_point(x, y, final_box.to_char(ascii_mode))
-				})
+	}
+
+	fn handles(&self) -> Vec<(isize, isize)> {
+		if !self.complete {
+			return Vec::new();
 		}
+
+		let mut handles = vec![self.start];
+		handles.extend(&self.waypoints);
+		handles.push(self.end);
+		handles
 	}
 
+	fn move_handle(&mut self, handle: usize, x: isize, y: isize) {
+		// Handles run from the start, through each waypoint, to the end
+		match handle {
+			0 => {
+				self.start = (x, y);
+				self.start_anchor = None;
+				self.snap_waypoints();
+			}
+			handle if handle <= self.waypoints.len() => {
+				self.waypoints[handle - 1] = (x, y);
+				self.snap_waypoints();
+			}
+			handle if handle == self.waypoints.len() + 1 => {
+				self.end = (x, y);
+				self.end_anchor = None;
+			}
+			_ => (),
+		}
+	}
+
+	fn anchored_ids(&self) -> Vec<u64> {
+		self.start_anchor
+			.iter()
+			.chain(&self.end_anchor)
+			.map(|anchor| anchor.id)
+			.collect()
+	}
+
+	fn keep_anchors(&mut self, ids: &[u64]) {
+		let live = |anchor: &Anchor| ids.contains(&anchor.id);
+		self.start_anchor = self.start_anchor.filter(live);
+		self.end_anchor = self.end_anchor.filter(live);
+	}
+
+	fn attach(&mut self, targets: &[AnchorTarget]) {
+		// Polylines keep their waypoints where they were put, so can't be routed around
+		if !self.complete || self.mode == LineMode::Polyline {
+			return;
+		}
+
+		let [(start, _), (end, _)] = self.ends();
+		self.start_anchor = self.start_anchor.or_else(|| Anchor::find(start, targets));
+		self.end_anchor = self.end_anchor.or_else(|| Anchor::find(end, targets));
+		if self.anchored() {
+			// Straight lines end where they were snapped to, rather than where the mouse was
+			self.end = end;
+			self.reroute(targets);
+		}
+	}
+
+	fn reroute(&mut self, targets: &[AnchorTarget]) {
+		// Anchors to rectangles which are gone, such as ones which have been undone, are kept in case
+		// they come back, leaving the line where it was until then
+		let bounds = |anchor: Anchor| {
+			targets
+				.iter()
+				.find(|(id, _)| *id == anchor.id)
+				.map(|(_, bounds)| *bounds)
+		};
+		if let Some(anchor) = self.start_anchor {
+			if let Some(bounds) = bounds(anchor) {
+				self.start = anchor.point(bounds);
+			}
+		}
+		if let Some(anchor) = self.end_anchor {
+			if let Some(bounds) = bounds(anchor) {
+				self.end = anchor.point(bounds);
+			}
+		}
+
+		// Leave and arrive at anchored sides at right angles, favouring the start
+		match (self.start_anchor, self.end_anchor) {
+			(Some(anchor), _) => self.vertical_first = anchor.vertical(),
+			(None, Some(anchor)) => self.vertical_first = !anchor.vertical(),
+			(None, None) => (),
+		}
+	}
+
+	fn set_style(&mut self, style: Style) { self.style = style; }
+
+	fn set_line_style(&mut self, line_style: LineStyle) { self.line_style = line_style; }
+
+	fn save(&self) -> Option<SavedTool> { Some(SavedTool::Line(self.clone())) }
+
 	fn complete(&self) -> bool { self.complete }
 }
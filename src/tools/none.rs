@@ -1,6 +1,11 @@
 use crossterm::event::{KeyEvent, MouseEventKind};
 
-use crate::{buffer::Buffer, state::State, tools::Tool};
+use crate::{
+	buffer::Buffer,
+	state::State,
+	style::Style,
+	tools::{SavedTool, Tool},
+};
 
 #[derive(Default)]
 pub struct None;
@@ -12,11 +17,21 @@ impl Tool for None {
 
 	fn key_event(&mut self, _: KeyEvent) -> fn(state: &mut State) { |_| () }
 
-	fn bounding_box(&self) -> Option<(usize, usize, usize, usize)> { Option::None }
+	fn bounding_box(&self) -> Option<(isize, isize, isize, isize)> { Option::None }
 
 	fn render(&self, _: &mut Buffer, _: bool) {}
 
-	fn render_bounded(&self, _: usize, _: usize, _: usize, _: usize, _: &mut Buffer, _: bool) {}
+	fn render_bounded(&self, _: isize, _: isize, _: isize, _: isize, _: &mut Buffer, _: bool) {}
+
+	fn translate(&mut self, _: isize, _: isize) {}
+
+	fn handles(&self) -> Vec<(isize, isize)> { Vec::new() }
+
+	fn move_handle(&mut self, _: usize, _: isize, _: isize) {}
+
+	fn set_style(&mut self, _: Style) {}
+
+	fn save(&self) -> Option<SavedTool> { Option::None }
 
 	fn complete(&self) -> bool { false }
 }
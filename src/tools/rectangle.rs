@@ -1,46 +1,53 @@
 use crossterm::event::{KeyEvent, MouseEventKind};
 
-use std::{convert::TryFrom, iter::once};
+use serde::{Deserialize, Serialize};
 
-use crate::{box_drawing::BoxFlags, buffer::Buffer, state::State, tools::Tool};
+use std::iter::once;
 
-#[derive(Default)]
+use crate::{
+	box_drawing::{BoxFlags, LineStyle},
+	buffer::Buffer,
+	state::State,
+	style::Style,
+	tools::{translate_point, SavedTool, Tool},
+};
+
+#[derive(Default, Clone, Serialize, Deserialize)]
 pub struct Rectangle {
 	started: bool,
-	start: (usize, usize),
-	end: (usize, usize),
+	start: (isize, isize),
+	end: (isize, isize),
 	complete: bool,
+	#[serde(default)]
+	style: Style,
+	#[serde(default)]
+	line_style: LineStyle,
+	/// Given once the rectangle is finished, for lines to be anchored to
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	id: Option<u64>,
 }
 
 impl Tool for Rectangle {
 	fn mouse_event(&mut self, x: isize, y: isize, kind: MouseEventKind) -> fn(state: &mut State) {
 		match kind {
 			MouseEventKind::Down(_) => {
-				if let (Ok(x), Ok(y)) = (usize::try_from(x), usize::try_from(y)) {
-					if !self.started {
-						self.start = (x, y);
-						self.end = (x, y);
-						self.started = true;
-						|_| ()
-					}
-					else {
-						// Edge case - dragged off edge then released mouse
-						self.end = (x, y);
-						self.complete = true;
-						|_| ()
-					}
+				if !self.started {
+					self.start = (x, y);
+					self.end = (x, y);
+					self.started = true;
 				}
 				else {
-					|_| ()
-				}
-			}
-			MouseEventKind::Drag(_) => {
-				if let (Ok(x), Ok(y)) = (usize::try_from(x), usize::try_from(y)) {
+					// Edge case - dragged off edge then released mouse
 					self.end = (x, y);
 					self.complete = true;
 				}
 				|_| ()
 			}
+			MouseEventKind::Drag(_) => {
+				self.end = (x, y);
+				self.complete = true;
+				|_| ()
+			}
 			MouseEventKind::Up(_) => |state| state.reset_current_mouse_element(),
 
 			_ => |_| (),
@@ -49,7 +56,7 @@ impl Tool for Rectangle {
 
 	fn key_event(&mut self, _: KeyEvent) -> fn(state: &mut State) { |_| () }
 
-	fn bounding_box(&self) -> Option<(usize, usize, usize, usize)> {
+	fn bounding_box(&self) -> Option<(isize, isize, isize, isize)> {
 		if self.started {
 			let (start_x, start_y) = self.start;
 			let (end_x, end_y) = self.end;
@@ -94,18 +101,17 @@ impl Tool for Rectangle {
 			.chain(once(bottom_left))
 			.chain(once(bottom_right))
 			.for_each(|(x, y, box_dir)| {
-				let current_box = BoxFlags::from_char(buffer.get_point(x, y), ascii_mode);
-				let final_box = box_dir | current_box;
-				buffer.render_point(x, y, final_box.to_char(ascii_mode))
+				let c = box_dir.draw(buffer.get_point(x, y), self.line_style, ascii_mode);
+				buffer.render_point(x, y, c, self.style)
 			})
 	}
 
 	fn render_bounded(
 		&self,
-		min_x: usize,
-		max_x: usize,
-		min_y: usize,
-		max_y: usize,
+		min_x: isize,
+		max_x: isize,
+		min_y: isize,
+		max_y: isize,
 		buffer: &mut Buffer,
 		ascii_mode: bool,
 	) {
@@ -138,11 +144,55 @@ impl Tool for Rectangle {
 			.chain(once(bottom_right))
 			.filter(|(x, y, _)| (min_x <= *x && *x < max_x) && (min_y <= *y && *y < max_y))
 			.for_each(|(x, y, box_dir)| {
-				let current_box = BoxFlags::from_char(buffer.get_point(x, y), ascii_mode);
-				let final_box = box_dir | current_box;
-				buffer.render_point(x, y, final_box.to_char(ascii_mode))
+				let c = box_dir.draw(buffer.get_point(x, y), self.line_style, ascii_mode);
+				buffer.render_point(x, y, c, self.style)
 			})
 	}
 
+	fn translate(&mut self, x: isize, y: isize) {
+		self.start = translate_point(self.start, x, y);
+		self.end = translate_point(self.end, x, y);
+	}
+
+	fn handles(&self) -> Vec<(isize, isize)> {
+		if !self.complete {
+			return Vec::new();
+		}
+
+		vec![
+			self.start,
+			(self.end.0, self.start.1),
+			(self.start.0, self.end.1),
+			self.end,
+		]
+	}
+
+	fn move_handle(&mut self, handle: usize, x: isize, y: isize) {
+		// Each corner shares its coordinates with the start and end points
+		match handle {
+			0 => self.start = (x, y),
+			1 => {
+				self.end.0 = x;
+				self.start.1 = y;
+			}
+			2 => {
+				self.start.0 = x;
+				self.end.1 = y;
+			}
+			3 => self.end = (x, y),
+			_ => (),
+		}
+	}
+
+	fn set_style(&mut self, style: Style) { self.style = style; }
+
+	fn set_line_style(&mut self, line_style: LineStyle) { self.line_style = line_style; }
+
+	fn anchor_id(&self) -> Option<u64> { self.id }
+
+	fn set_anchor_id(&mut self, id: u64) { self.id = self.id.or(Some(id)); }
+
+	fn save(&self) -> Option<SavedTool> { Some(SavedTool::Rectangle(self.clone())) }
+
 	fn complete(&self) -> bool { self.complete }
 }
@@ -1,16 +1,23 @@
 use crossterm::event::{KeyCode, KeyEvent, MouseEventKind};
 
-use std::convert::TryFrom;
+use serde::{Deserialize, Serialize};
 
-use crate::{buffer::Buffer, state::State, tools::Tool};
+use crate::{
+	buffer::Buffer,
+	state::State,
+	style::Style,
+	tools::{translate_point, SavedTool, Tool},
+};
 
-#[derive(Default)]
+#[derive(Default, Clone, Serialize, Deserialize)]
 pub struct Text {
-	x: usize,
-	y: usize,
+	x: isize,
+	y: isize,
 	text: Vec<String>,
 	in_progress: bool,
 	finished: bool,
+	#[serde(default)]
+	style: Style,
 }
 
 impl Tool for Text {
@@ -18,11 +25,9 @@ impl Tool for Text {
 		match kind {
 			MouseEventKind::Down(_) => {
 				if !self.in_progress {
-					if let (Ok(x), Ok(y)) = (usize::try_from(x), usize::try_from(y)) {
-						self.x = x;
-						self.y = y;
-						self.in_progress = true;
-					}
+					self.x = x;
+					self.y = y;
+					self.in_progress = true;
 					|_| ()
 				}
 				else {
@@ -41,6 +46,7 @@ impl Tool for Text {
 			KeyEvent {
 				code: KeyCode::Esc,
 				modifiers: _,
+				..
 			} => {
 				self.in_progress = false;
 				self.finished = true;
@@ -49,9 +55,10 @@ impl Tool for Text {
 			KeyEvent {
 				code: KeyCode::Backspace,
 				modifiers: _,
+				..
 			} => {
 				if let Some(line) = self.text.last_mut() {
-					if line.len() == 0 {
+					if line.is_empty() {
 						self.text.pop();
 					}
 					else {
@@ -63,6 +70,7 @@ impl Tool for Text {
 			KeyEvent {
 				code: KeyCode::Enter,
 				modifiers: _,
+				..
 			} => {
 				self.text.push(String::new());
 				|_| ()
@@ -70,6 +78,7 @@ impl Tool for Text {
 			KeyEvent {
 				code: KeyCode::Char(c),
 				modifiers: _,
+				..
 			} => {
 				if let Some(line) = self.text.last_mut() {
 					line.push(c);
@@ -84,13 +93,24 @@ impl Tool for Text {
 		}
 	}
 
-	fn bounding_box(&self) -> Option<(usize, usize, usize, usize)> {
+	fn paste(&mut self, text: &str) {
+		for (index, pasted) in text.split('\n').enumerate() {
+			if index > 0 || self.text.is_empty() {
+				self.text.push(String::new());
+			}
+			if let Some(line) = self.text.last_mut() {
+				line.extend(pasted.chars().filter(|c| !c.is_control()));
+			}
+		}
+	}
+
+	fn bounding_box(&self) -> Option<(isize, isize, isize, isize)> {
 		let longest_line = self.text.iter().map(|line| line.len()).max().unwrap_or(0);
 		Some((
 			self.x,
-			self.x + longest_line,
+			self.x + longest_line as isize,
 			self.y,
-			self.y + self.text.len(),
+			self.y + self.text.len() as isize,
 		))
 	}
 
@@ -98,42 +118,57 @@ impl Tool for Text {
 		self.text.iter().enumerate().for_each(|(y, line)| {
 			line.chars()
 				.enumerate()
-				.map(|(x, c)| (self.x + x, self.y + y, c))
-				.for_each(|(x, y, c)| buffer.render_point(x, y, c));
+				.map(|(x, c)| (self.x + x as isize, self.y + y as isize, c))
+				.for_each(|(x, y, c)| buffer.render_point(x, y, c, self.style));
 		});
 		if self.in_progress {
 			buffer.render_point(
-				self.x + self.text.last().map(|l| l.len()).unwrap_or(0),
-				self.y + self.text.len().saturating_sub(1),
+				self.x + self.text.last().map(|l| l.len()).unwrap_or(0) as isize,
+				self.y + self.text.len().saturating_sub(1) as isize,
 				'<',
+				self.style,
 			)
 		}
 	}
 
 	fn render_bounded(
 		&self,
-		min_x: usize,
-		max_x: usize,
-		min_y: usize,
-		max_y: usize,
+		min_x: isize,
+		max_x: isize,
+		min_y: isize,
+		max_y: isize,
 		buffer: &mut Buffer,
 		_: bool,
 	) {
 		self.text.iter().enumerate().for_each(|(y, line)| {
 			line.chars()
 				.enumerate()
-				.map(|(x, c)| (self.x + x, self.y + y, c))
+				.map(|(x, c)| (self.x + x as isize, self.y + y as isize, c))
 				.filter(|(x, y, _)| (min_x <= *x && *x < max_x) && (min_y <= *y && *y < max_y))
-				.for_each(|(x, y, c)| buffer.render_point(x, y, c));
+				.for_each(|(x, y, c)| buffer.render_point(x, y, c, self.style));
 		});
 		if self.in_progress {
-			buffer.render_point(
-				self.x + self.text.last().map(|l| l.len()).unwrap_or(0),
-				self.y + self.text.len().saturating_sub(1),
-				'<',
-			)
+			let x = self.x + self.text.last().map(|l| l.len()).unwrap_or(0) as isize;
+			let y = self.y + self.text.len().saturating_sub(1) as isize;
+			if (min_x <= x && x < max_x) && (min_y <= y && y < max_y) {
+				buffer.render_point(x, y, '<', self.style)
+			}
 		}
 	}
 
+	fn translate(&mut self, x: isize, y: isize) {
+		let (new_x, new_y) = translate_point((self.x, self.y), x, y);
+		self.x = new_x;
+		self.y = new_y;
+	}
+
+	fn handles(&self) -> Vec<(isize, isize)> { Vec::new() }
+
+	fn move_handle(&mut self, _: usize, _: isize, _: isize) {}
+
+	fn set_style(&mut self, style: Style) { self.style = style; }
+
+	fn save(&self) -> Option<SavedTool> { Some(SavedTool::Text(self.clone())) }
+
 	fn complete(&self) -> bool { self.finished }
 }
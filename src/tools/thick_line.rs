@@ -2,47 +2,46 @@ use crossterm::event::{KeyEvent, MouseEventKind};
 
 use line_drawing::Bresenham;
 
-use std::convert::TryFrom;
+use serde::{Deserialize, Serialize};
 
-use crate::{buffer::Buffer, state::State, tools::Tool};
+use crate::{
+	buffer::Buffer,
+	state::State,
+	style::Style,
+	tools::{translate_point, SavedTool, Tool},
+};
 
-#[derive(Default)]
+#[derive(Default, Clone, Serialize, Deserialize)]
 pub struct ThickLine {
 	started: bool,
-	start: (usize, usize),
-	end: (usize, usize),
+	start: (isize, isize),
+	end: (isize, isize),
 	complete: bool,
+	#[serde(default)]
+	style: Style,
 }
 
 impl Tool for ThickLine {
 	fn mouse_event(&mut self, x: isize, y: isize, kind: MouseEventKind) -> fn(state: &mut State) {
 		match kind {
 			MouseEventKind::Down(_) => {
-				if let (Ok(x), Ok(y)) = (usize::try_from(x), usize::try_from(y)) {
-					if !self.started {
-						self.start = (x, y);
-						self.end = (x, y);
-						self.started = true;
-						|_| ()
-					}
-					else {
-						// Edge case - dragged off edge then released mouse
-						self.end = (x, y);
-						self.complete = true;
-						|_| ()
-					}
+				if !self.started {
+					self.start = (x, y);
+					self.end = (x, y);
+					self.started = true;
 				}
 				else {
-					|_| ()
-				}
-			}
-			MouseEventKind::Drag(_) => {
-				if let (Ok(x), Ok(y)) = (usize::try_from(x), usize::try_from(y)) {
+					// Edge case - dragged off edge then released mouse
 					self.end = (x, y);
 					self.complete = true;
 				}
 				|_| ()
 			}
+			MouseEventKind::Drag(_) => {
+				self.end = (x, y);
+				self.complete = true;
+				|_| ()
+			}
 			MouseEventKind::Up(_) => |state| state.reset_current_mouse_element(),
 
 			_ => |_| (),
@@ -51,7 +50,7 @@ impl Tool for ThickLine {
 
 	fn key_event(&mut self, _: KeyEvent) -> fn(state: &mut State) { |_| () }
 
-	fn bounding_box(&self) -> Option<(usize, usize, usize, usize)> {
+	fn bounding_box(&self) -> Option<(isize, isize, isize, isize)> {
 		if self.started {
 			let (start_x, start_y) = self.start;
 			let (end_x, end_y) = self.end;
@@ -72,25 +71,17 @@ impl Tool for ThickLine {
 			return;
 		}
 
-		let (start_x, start_y) = self.start;
-		let (end_x, end_y) = self.end;
-
-		for (x, y) in Bresenham::new(
-			(start_x as isize, start_y as isize),
-			(end_x as isize, end_y as isize),
-		)
-		.map(|(x, y)| (x as usize, y as usize))
-		{
-			buffer.render_point(x, y, if ascii_mode { '#' } else { '█' })
+		for (x, y) in Bresenham::new(self.start, self.end) {
+			buffer.render_point(x, y, if ascii_mode { '#' } else { '█' }, self.style)
 		}
 	}
 
 	fn render_bounded(
 		&self,
-		min_x: usize,
-		max_x: usize,
-		min_y: usize,
-		max_y: usize,
+		min_x: isize,
+		max_x: isize,
+		min_y: isize,
+		max_y: isize,
 		buffer: &mut Buffer,
 		ascii_mode: bool,
 	) {
@@ -98,19 +89,25 @@ impl Tool for ThickLine {
 			return;
 		}
 
-		let (start_x, start_y) = self.start;
-		let (end_x, end_y) = self.end;
-
-		for (x, y) in Bresenham::new(
-			(start_x as isize, start_y as isize),
-			(end_x as isize, end_y as isize),
-		)
-		.map(|(x, y)| (x as usize, y as usize))
-		.filter(|(x, y)| (min_x <= *x && *x < max_x) && (min_y <= *y && *y < max_y))
+		for (x, y) in Bresenham::new(self.start, self.end)
+			.filter(|(x, y)| (min_x <= *x && *x < max_x) && (min_y <= *y && *y < max_y))
 		{
-			buffer.render_point(x, y, if ascii_mode { '#' } else { '█' })
+			buffer.render_point(x, y, if ascii_mode { '#' } else { '█' }, self.style)
 		}
 	}
 
+	fn translate(&mut self, x: isize, y: isize) {
+		self.start = translate_point(self.start, x, y);
+		self.end = translate_point(self.end, x, y);
+	}
+
+	fn handles(&self) -> Vec<(isize, isize)> { Vec::new() }
+
+	fn move_handle(&mut self, _: usize, _: isize, _: isize) {}
+
+	fn set_style(&mut self, style: Style) { self.style = style; }
+
+	fn save(&self) -> Option<SavedTool> { Some(SavedTool::ThickLine(self.clone())) }
+
 	fn complete(&self) -> bool { self.complete }
 }
@@ -0,0 +1,695 @@
+use crossterm::event::{
+	Event, KeyCode, KeyEvent, KeyModifiers, MouseButton, MouseEvent, MouseEventKind,
+};
+
+use tpaint::headless::{BlockSelector, Colour, ColourDepth, Region, Session, Style};
+
+fn mouse(kind: MouseEventKind, column: u16, row: u16) -> Event {
+	Event::Mouse(MouseEvent {
+		kind,
+		column,
+		row,
+		modifiers: KeyModifiers::NONE,
+	})
+}
+
+fn click_menu(session: &mut Session, label: &str) {
+	let column = session.screen().lines()[0].find(label).unwrap() as u16;
+	session
+		.handle_event(mouse(MouseEventKind::Down(MouseButton::Left), column, 0))
+		.unwrap();
+}
+
+fn drag(session: &mut Session, from: (u16, u16), to: (u16, u16)) {
+	session
+		.handle_event(mouse(
+			MouseEventKind::Down(MouseButton::Left),
+			from.0,
+			from.1,
+		))
+		.unwrap();
+	session
+		.handle_event(mouse(MouseEventKind::Drag(MouseButton::Left), to.0, to.1))
+		.unwrap();
+	session
+		.handle_event(mouse(MouseEventKind::Up(MouseButton::Left), to.0, to.1))
+		.unwrap();
+}
+
+#[test]
+fn draws_rectangle() {
+	let mut session = Session::new(40, 12).unwrap();
+	click_menu(&mut session, "[]");
+	drag(&mut session, (2, 3), (8, 5));
+
+	let lines = session.screen().lines();
+	assert!(lines[1].starts_with("Rectangle"));
+	assert_eq!(
+		&lines[3].chars().skip(2).take(7).collect::<String>(),
+		"┏━━━━━┓"
+	);
+	assert_eq!(
+		&lines[4].chars().skip(2).take(7).collect::<String>(),
+		"┃     ┃"
+	);
+	assert_eq!(
+		&lines[5].chars().skip(2).take(7).collect::<String>(),
+		"┗━━━━━┛"
+	);
+}
+
+#[test]
+fn undo_and_redo_update_screen() {
+	let mut session = Session::new(40, 12).unwrap();
+	click_menu(&mut session, "-");
+	drag(&mut session, (2, 3), (8, 3));
+	assert!(session.screen().lines()[1].contains("undo 1 | redo 0"));
+
+	let key = |c| Event::Key(KeyEvent::new(KeyCode::Char(c), KeyModifiers::NONE));
+	session.handle_event(key('z')).unwrap();
+	assert!(session.screen().lines()[1].contains("undo 0 | redo 1"));
+	assert!(!session.screen().lines()[3].contains('━'));
+
+	session.handle_event(key('Z')).unwrap();
+	assert!(session.screen().lines()[1].contains("undo 1 | redo 0"));
+	assert!(session.screen().lines()[3].contains("╺━━━━━╸"));
+}
+
+#[test]
+fn draws_above_and_left_of_origin() {
+	let mut session = Session::new(40, 12).unwrap();
+	click_menu(&mut session, "-");
+	drag(&mut session, (2, 3), (8, 3));
+
+	// Pan the view so that the top left of the workspace shows negative coordinates
+	session
+		.handle_event(mouse(MouseEventKind::Down(MouseButton::Right), 10, 6))
+		.unwrap();
+	session
+		.handle_event(mouse(MouseEventKind::Drag(MouseButton::Right), 14, 8))
+		.unwrap();
+	session
+		.handle_event(mouse(MouseEventKind::Up(MouseButton::Right), 14, 8))
+		.unwrap();
+
+	click_menu(&mut session, "[]");
+	drag(&mut session, (2, 2), (4, 4));
+
+	assert_eq!(
+		session.render_to_file(false),
+		"┏━┓\n┃ ┃\n┗━┛\n    ╺━━━━━╸\n"
+	);
+}
+
+#[test]
+fn draws_in_picked_colour() {
+	let mut session = Session::new(80, 12).unwrap();
+
+	// The foreground swatches follow the default swatch, starting with black then red
+	let red = session.screen().lines()[0].find("fg ").unwrap() as u16 + 5;
+	session
+		.handle_event(mouse(MouseEventKind::Down(MouseButton::Left), red, 0))
+		.unwrap();
+	session
+		.handle_event(mouse(MouseEventKind::Up(MouseButton::Left), red, 0))
+		.unwrap();
+
+	click_menu(&mut session, "[]");
+	drag(&mut session, (2, 3), (8, 5));
+
+	assert_eq!(session.screen().style(2, 3).foreground, Some(Colour::Red));
+	assert_eq!(session.screen().style(2, 6).foreground, None);
+	assert_eq!(session.render_to_file(false), "┏━━━━━┓\n┃     ┃\n┗━━━━━┛\n");
+	assert!(session
+		.render_to_ansi(false, ColourDepth::Basic)
+		.starts_with("\x1b[0;31m┏━━━━━┓\x1b[0m\n"));
+}
+
+#[test]
+fn fits_colour_picker_beside_menu() {
+	let mut session = Session::new(80, 12).unwrap();
+	let click = |session: &mut Session, column: usize| {
+		for kind in [
+			MouseEventKind::Down(MouseButton::Left),
+			MouseEventKind::Up(MouseButton::Left),
+		] {
+			session.handle_event(mouse(kind, column as u16, 0)).unwrap();
+		}
+	};
+	let menu = session.screen().lines()[0].chars().collect::<Vec<_>>();
+	let find = |label: &str| {
+		let label = label.chars().collect::<Vec<_>>();
+		menu.windows(label.len())
+			.position(|window| window == label)
+			.unwrap()
+	};
+	let (fg, bg, bold, dim) = (find("fg ") + 3, find(" bg ") + 4, find("B"), find("D"));
+	assert_eq!(menu[dim + 1..].iter().filter(|c| **c != ' ').count(), 0);
+
+	// The default swatch and each colour after it, for both the foreground and background
+	for swatch in (fg..fg + 9).chain(bg..bg + 9) {
+		click(&mut session, swatch);
+		assert_eq!(session.screen().lines()[0].chars().nth(swatch), Some('•'));
+	}
+	click(&mut session, bold);
+	click(&mut session, dim);
+	assert!(session.screen().is_reversed(bold as u16, 0));
+	assert!(session.screen().is_reversed(dim as u16, 0));
+
+	click_menu(&mut session, "[]");
+	drag(&mut session, (2, 3), (4, 5));
+	assert_eq!(
+		session.screen().style(2, 3),
+		Style {
+			foreground: Some(Colour::White),
+			background: Some(Colour::White),
+			bold: true,
+			dim: true,
+		}
+	);
+}
+
+#[test]
+fn joins_lines_of_different_styles() {
+	let mut session = Session::new(40, 12).unwrap();
+	let key = |c| Event::Key(KeyEvent::new(KeyCode::Char(c), KeyModifiers::NONE));
+
+	session.handle_event(key('l')).unwrap();
+	assert!(session.screen().lines()[1].contains("light lines"));
+	click_menu(&mut session, "[]");
+	drag(&mut session, (2, 3), (8, 5));
+
+	session.handle_event(key('l')).unwrap();
+	click_menu(&mut session, "-");
+	drag(&mut session, (1, 4), (9, 4));
+
+	assert_eq!(
+		session.render_to_file(false),
+		" ┌─────┐\n═╪═════╪═\n └─────┘\n"
+	);
+}
+
+#[test]
+fn draws_line_end_markers() {
+	let mut session = Session::new(40, 12).unwrap();
+	let key = |c| Event::Key(KeyEvent::new(KeyCode::Char(c), KeyModifiers::NONE));
+
+	click_menu(&mut session, "-");
+	session
+		.handle_event(mouse(MouseEventKind::Down(MouseButton::Left), 2, 3))
+		.unwrap();
+	session
+		.handle_event(mouse(MouseEventKind::Drag(MouseButton::Left), 8, 3))
+		.unwrap();
+	session.handle_event(key('>')).unwrap();
+	session.handle_event(key('<')).unwrap();
+	session.handle_event(key('<')).unwrap();
+	session
+		.handle_event(mouse(MouseEventKind::Up(MouseButton::Left), 8, 3))
+		.unwrap();
+
+	assert_eq!(session.render_to_file(false), "●━━━━━▶\n");
+	assert_eq!(session.render_to_file(true), "o----->\n");
+}
+
+#[test]
+fn draws_elbow_lines() {
+	let mut session = Session::new(60, 12).unwrap();
+	let key = |c| Event::Key(KeyEvent::new(KeyCode::Char(c), KeyModifiers::NONE));
+
+	click_menu(&mut session, "L");
+	drag(&mut session, (2, 3), (6, 5));
+
+	session
+		.handle_event(mouse(MouseEventKind::Down(MouseButton::Left), 2, 7))
+		.unwrap();
+	session
+		.handle_event(mouse(MouseEventKind::Drag(MouseButton::Left), 6, 9))
+		.unwrap();
+	session.handle_event(key(' ')).unwrap();
+	session
+		.handle_event(mouse(MouseEventKind::Up(MouseButton::Left), 6, 9))
+		.unwrap();
+
+	assert_eq!(
+		session.render_to_file(false),
+		"╺━━━┓\n    ┃\n    ╹\n\n╻\n┃\n┗━━━╸\n"
+	);
+}
+
+#[test]
+fn draws_polylines_until_finished() {
+	let mut session = Session::new(60, 12).unwrap();
+
+	click_menu(&mut session, "N");
+	for (kind, x, y) in [
+		(MouseEventKind::Down(MouseButton::Left), 2, 3),
+		(MouseEventKind::Up(MouseButton::Left), 2, 3),
+		(MouseEventKind::Moved, 7, 3),
+		(MouseEventKind::Down(MouseButton::Left), 7, 3),
+		(MouseEventKind::Up(MouseButton::Left), 7, 3),
+		(MouseEventKind::Moved, 7, 5),
+		(MouseEventKind::Down(MouseButton::Left), 7, 5),
+		(MouseEventKind::Up(MouseButton::Left), 7, 5),
+		(MouseEventKind::Moved, 10, 5),
+		(MouseEventKind::Down(MouseButton::Left), 10, 5),
+		(MouseEventKind::Up(MouseButton::Left), 10, 5),
+		(MouseEventKind::Moved, 10, 8),
+	] {
+		session.handle_event(mouse(kind, x, y)).unwrap();
+	}
+	assert!(session.screen().lines()[8].contains('╹'));
+
+	// The line ends at the last click rather than where the mouse went after it
+	session
+		.handle_event(Event::Key(KeyEvent::new(
+			KeyCode::Enter,
+			KeyModifiers::NONE,
+		)))
+		.unwrap();
+
+	assert_eq!(session.render_to_file(false), "╺━━━━┓\n     ┃\n     ┗━━╸\n");
+}
+
+#[test]
+fn keeps_polyline_handles_on_the_line() {
+	let mut session = Session::new(60, 12).unwrap();
+	let enter = Event::Key(KeyEvent::new(KeyCode::Enter, KeyModifiers::NONE));
+
+	// Something to measure against, as the drawing is trimmed to what is on it
+	click_menu(&mut session, "[]");
+	drag(&mut session, (2, 8), (3, 9));
+
+	click_menu(&mut session, "N");
+	for (kind, x, y) in [
+		(MouseEventKind::Down(MouseButton::Left), 2, 3),
+		(MouseEventKind::Up(MouseButton::Left), 2, 3),
+		(MouseEventKind::Down(MouseButton::Left), 7, 4),
+		(MouseEventKind::Up(MouseButton::Left), 7, 4),
+		(MouseEventKind::Down(MouseButton::Left), 7, 6),
+		(MouseEventKind::Up(MouseButton::Left), 7, 6),
+	] {
+		session.handle_event(mouse(kind, x, y)).unwrap();
+	}
+	session.handle_event(enter).unwrap();
+	assert_eq!(
+		session.render_to_file(false),
+		"╺━━━━┓\n     ┃\n     ┃\n     ╹\n\n┏┓\n┗┛\n"
+	);
+
+	// The corner is dragged by the handle where it is drawn rather than where it was clicked
+	click_menu(&mut session, "^");
+	drag(&mut session, (4, 3), (4, 3));
+	drag(&mut session, (7, 3), (9, 3));
+	assert_eq!(
+		session.render_to_file(false),
+		"╺━━━━━━┓\n       ┃\n       ┃\n       ╹\n\n┏┓\n┗┛\n"
+	);
+}
+
+#[test]
+fn finishes_or_cancels_polylines_from_the_keyboard() {
+	let mut session = Session::new(60, 12).unwrap();
+	let key = |code| Event::Key(KeyEvent::new(code, KeyModifiers::NONE));
+	let click = |session: &mut Session, x, y| {
+		for kind in [
+			MouseEventKind::Down(MouseButton::Left),
+			MouseEventKind::Up(MouseButton::Left),
+		] {
+			session.handle_event(mouse(kind, x, y)).unwrap();
+		}
+	};
+
+	// Escape throws the line being drawn away, however many corners it has
+	click_menu(&mut session, "N");
+	click(&mut session, 2, 3);
+	click(&mut session, 6, 3);
+	session
+		.handle_event(mouse(MouseEventKind::Moved, 6, 5))
+		.unwrap();
+	session.handle_event(key(KeyCode::Esc)).unwrap();
+	assert_eq!(session.render_to_file(false), "");
+
+	// Enter keeps drawing until a second click takes the line somewhere, then finishes it
+	click(&mut session, 2, 3);
+	session.handle_event(key(KeyCode::Enter)).unwrap();
+	session
+		.handle_event(mouse(MouseEventKind::Moved, 6, 3))
+		.unwrap();
+	session.handle_event(key(KeyCode::Enter)).unwrap();
+	click(&mut session, 6, 3);
+	session.handle_event(key(KeyCode::Enter)).unwrap();
+	assert_eq!(session.render_to_file(false), "╺━━━╸\n");
+}
+
+#[test]
+fn anchored_lines_follow_rectangles() {
+	let mut session = Session::new(60, 12).unwrap();
+
+	click_menu(&mut session, "[]");
+	drag(&mut session, (2, 3), (8, 6));
+	click_menu(&mut session, "-");
+	drag(&mut session, (8, 4), (14, 4));
+
+	click_menu(&mut session, "^");
+	drag(&mut session, (3, 3), (3, 5));
+
+	assert_eq!(
+		session.render_to_file(false),
+		"            ╻\n┏━━━━━┓     ┃\n┃     ┣━━━━━┛\n┃     ┃\n┗━━━━━┛\n"
+	);
+}
+
+#[test]
+fn draws_diagonal_lines_with_slashes() {
+	let mut session = Session::new(60, 12).unwrap();
+
+	click_menu(&mut session, "[]");
+	drag(&mut session, (4, 2), (8, 4));
+	click_menu(&mut session, "/");
+	drag(&mut session, (6, 4), (3, 7));
+	drag(&mut session, (6, 4), (9, 7));
+	drag(&mut session, (11, 5), (13, 7));
+	drag(&mut session, (13, 5), (11, 7));
+	drag(&mut session, (10, 2), (16, 4));
+
+	assert_eq!(
+		session.render_to_file(false),
+		" ┏━━━┓ ╺━╲\n ┃   ┃    ━━╲\n ┗━━━┛       ╲\n  ╱ ╲   ╲ ╱\n ╱   ╲   ╳\n╱     ╲ ╱ ╲\n"
+	);
+	assert_eq!(
+		session.render_to_file(true),
+		" +---+ --\\\n |   |    --\\\n +---+       \\\n  / \\   \\ /\n /   \\   X\n/     \\ / \\\n"
+	);
+}
+
+#[test]
+fn reopens_document_saved_alongside_text_file() {
+	let directory = std::env::temp_dir().join(format!("tpaint-reopen-{}", std::process::id()));
+	std::fs::create_dir_all(&directory).unwrap();
+	let file = directory.join("drawing.txt");
+	std::fs::write(&file, "hello\n").unwrap();
+	let key = |c| Event::Key(KeyEvent::new(KeyCode::Char(c), KeyModifiers::NONE));
+	let drawing = "hello\n\n┏━┓\n┃ ┃\n┗━┛\n";
+
+	// The first drag after opening draws a new object rather than going to the loaded text
+	let mut session = Session::open(60, 12, file.clone()).unwrap();
+	click_menu(&mut session, "[]");
+	drag(&mut session, (0, 4), (2, 6));
+	assert_eq!(session.render_to_file(false), drawing);
+	session.handle_event(key('s')).unwrap();
+	session.handle_event(key('e')).unwrap();
+
+	// Reopening the text file picks up the document, keeping the rectangle as an object
+	let mut session = Session::open(60, 12, file.clone()).unwrap();
+	assert_eq!(session.render_to_file(false), drawing);
+	click_menu(&mut session, "^");
+	drag(&mut session, (1, 4), (5, 4));
+	assert_eq!(
+		session.render_to_file(false),
+		"hello\n\n    ┏━┓\n    ┃ ┃\n    ┗━┛\n"
+	);
+
+	// Once the text file has been edited elsewhere the document is out of date, and the file wins
+	std::fs::write(&file, "changed\n").unwrap();
+	let mut session = Session::open(60, 12, file.clone()).unwrap();
+	assert_eq!(session.render_to_file(false), "changed\n");
+	session.handle_event(key('e')).unwrap();
+	assert_eq!(std::fs::read_to_string(&file).unwrap(), "changed\n");
+
+	std::fs::remove_dir_all(directory).unwrap();
+}
+
+#[test]
+fn reopens_markdown_block_edited_elsewhere() {
+	let directory = std::env::temp_dir().join(format!("tpaint-reorder-{}", std::process::id()));
+	std::fs::create_dir_all(&directory).unwrap();
+	let file = directory.join("notes.md");
+	let key = |c| Event::Key(KeyEvent::new(KeyCode::Char(c), KeyModifiers::NONE));
+	let first = || Region::Block("1".parse::<BlockSelector>().unwrap());
+	std::fs::write(&file, "```\nfirst\n```\n\n```\nsecond\n```\n").unwrap();
+
+	let mut session = Session::open_region(40, 12, file.clone(), first()).unwrap();
+	click_menu(&mut session, "[]");
+	drag(&mut session, (0, 4), (2, 5));
+	session.handle_event(key('s')).unwrap();
+	session.handle_event(key('e')).unwrap();
+	let drawn = "```\nfirst\n\n+-+\n+-+\n```\n\n```\nsecond\n```\n";
+	assert_eq!(std::fs::read_to_string(&file).unwrap(), drawn);
+
+	// Unchanged, the block opens with the objects it was drawn with
+	let session = Session::open_region(40, 12, file.clone(), first()).unwrap();
+	assert_eq!(session.render_to_file(false), "first\n\n┏━┓\n┗━┛\n");
+
+	// Reordered by hand, the first block is now a different one which is opened as it is, and
+	// exporting it leaves the file alone
+	let reordered = "```\nsecond\n```\n\n```\nfirst\n\n+-+\n+-+\n```\n";
+	std::fs::write(&file, reordered).unwrap();
+	let mut session = Session::open_region(40, 12, file.clone(), first()).unwrap();
+	assert_eq!(session.render_to_file(false), "second\n");
+	session.handle_event(key('e')).unwrap();
+	assert_eq!(std::fs::read_to_string(&file).unwrap(), reordered);
+
+	std::fs::remove_dir_all(directory).unwrap();
+}
+
+#[test]
+fn anchored_lines_find_rectangles_brought_back() {
+	let mut session = Session::new(60, 12).unwrap();
+	let key = |c| Event::Key(KeyEvent::new(KeyCode::Char(c), KeyModifiers::NONE));
+
+	click_menu(&mut session, "[]");
+	drag(&mut session, (2, 3), (8, 6));
+	click_menu(&mut session, "-");
+	drag(&mut session, (8, 4), (14, 4));
+
+	// Deleting the rectangle leaves the line anchored to it, ready for it to come back
+	click_menu(&mut session, "^");
+	drag(&mut session, (3, 3), (3, 3));
+	session
+		.handle_event(Event::Key(KeyEvent::new(
+			KeyCode::Delete,
+			KeyModifiers::NONE,
+		)))
+		.unwrap();
+	session.handle_event(key('z')).unwrap();
+	session.handle_event(key('Z')).unwrap();
+
+	// A new rectangle drawn while it is gone doesn't take over its id
+	click_menu(&mut session, "[]");
+	drag(&mut session, (20, 3), (22, 5));
+	assert_eq!(
+		session.render_to_file(false),
+		"            ┏━┓\n╺━━━━━╸     ┃ ┃\n            ┗━┛\n"
+	);
+	session.handle_event(key('z')).unwrap();
+	session.handle_event(key('z')).unwrap();
+
+	click_menu(&mut session, "^");
+	drag(&mut session, (3, 3), (3, 5));
+	assert_eq!(
+		session.render_to_file(false),
+		"            ╻\n┏━━━━━┓     ┃\n┃     ┣━━━━━┛\n┃     ┃\n┗━━━━━┛\n"
+	);
+}
+
+#[test]
+fn saves_anchors_only_while_rectangles_can_come_back() {
+	let directory = std::env::temp_dir().join(format!("tpaint-anchors-{}", std::process::id()));
+	std::fs::create_dir_all(&directory).unwrap();
+	let file = directory.join("drawing.txt");
+	let document = directory.join("drawing.tpaint");
+	let key = |code| Event::Key(KeyEvent::new(code, KeyModifiers::NONE));
+
+	std::fs::write(&file, "").unwrap();
+
+	let mut session = Session::open(60, 12, file.clone()).unwrap();
+	click_menu(&mut session, "[]");
+	drag(&mut session, (2, 3), (8, 6));
+	click_menu(&mut session, "-");
+	drag(&mut session, (8, 4), (14, 4));
+	click_menu(&mut session, "^");
+	drag(&mut session, (3, 3), (3, 3));
+	session.handle_event(key(KeyCode::Delete)).unwrap();
+
+	// The deleted rectangle can still be undone back, so the line stays anchored to it
+	session.handle_event(key(KeyCode::Char('s'))).unwrap();
+	session.handle_event(key(KeyCode::Char('e'))).unwrap();
+	assert!(std::fs::read_to_string(&document)
+		.unwrap()
+		.contains("start_anchor"));
+
+	// Opened again without any history, nothing can bring it back
+	let mut session = Session::open(60, 12, file.clone()).unwrap();
+	assert_eq!(session.render_to_file(false), "╺━━━━━╸\n");
+	session.handle_event(key(KeyCode::Char('s'))).unwrap();
+	assert!(!std::fs::read_to_string(&document)
+		.unwrap()
+		.contains("anchor"));
+
+	let session = Session::open(60, 12, file).unwrap();
+	assert_eq!(session.render_to_file(false), "╺━━━━━╸\n");
+
+	std::fs::remove_dir_all(directory).unwrap();
+}
+
+#[test]
+fn selects_through_cut_areas() {
+	let mut session = Session::new(60, 12).unwrap();
+	let key = |code| Event::Key(KeyEvent::new(code, KeyModifiers::NONE));
+
+	click_menu(&mut session, "[]");
+	drag(&mut session, (2, 3), (4, 5));
+	click_menu(&mut session, "-");
+	drag(&mut session, (2, 8), (5, 8));
+
+	// Cutting the rectangle out leaves nothing there to be selected and deleted
+	click_menu(&mut session, "^");
+	drag(&mut session, (1, 2), (5, 6));
+	session.handle_event(key(KeyCode::Char('x'))).unwrap();
+	assert_eq!(session.render_to_file(false), "╺━━╸\n");
+	drag(&mut session, (3, 3), (3, 3));
+	session.handle_event(key(KeyCode::Delete)).unwrap();
+	assert_eq!(session.render_to_file(false), "╺━━╸\n");
+
+	// Dragging from inside the cut area selects a region rather than moving the cut
+	drag(&mut session, (5, 6), (6, 9));
+	session.handle_event(key(KeyCode::Char('x'))).unwrap();
+	assert_eq!(session.render_to_file(false), "╺━━\n");
+}
+
+#[test]
+fn undoes_deleting_and_moving_objects() {
+	let mut session = Session::new(60, 12).unwrap();
+	let key = |c| Event::Key(KeyEvent::new(KeyCode::Char(c), KeyModifiers::NONE));
+	let drawing = "┏━┓\n┃ ┃\n┗━┛\n\n╺━━╸\n";
+
+	click_menu(&mut session, "[]");
+	drag(&mut session, (2, 3), (4, 5));
+	click_menu(&mut session, "-");
+	drag(&mut session, (2, 7), (5, 7));
+	assert_eq!(session.render_to_file(false), drawing);
+
+	click_menu(&mut session, "^");
+	drag(&mut session, (3, 3), (3, 3));
+	session
+		.handle_event(Event::Key(KeyEvent::new(
+			KeyCode::Delete,
+			KeyModifiers::NONE,
+		)))
+		.unwrap();
+	assert_eq!(session.render_to_file(false), "╺━━╸\n");
+	session.handle_event(key('z')).unwrap();
+	assert_eq!(session.render_to_file(false), drawing);
+
+	drag(&mut session, (3, 3), (9, 3));
+	assert_eq!(
+		session.render_to_file(false),
+		"      ┏━┓\n      ┃ ┃\n      ┗━┛\n\n╺━━╸\n"
+	);
+	session.handle_event(key('z')).unwrap();
+	assert_eq!(session.render_to_file(false), drawing);
+	assert!(session.screen().lines()[1].contains("undo 2 | redo 1"));
+	session.handle_event(key('Z')).unwrap();
+	assert_eq!(
+		session.render_to_file(false),
+		"      ┏━┓\n      ┃ ┃\n      ┗━┛\n\n╺━━╸\n"
+	);
+}
+
+#[test]
+fn undoes_resizing_with_a_handle() {
+	let mut session = Session::new(60, 12).unwrap();
+	let key = |c| Event::Key(KeyEvent::new(KeyCode::Char(c), KeyModifiers::NONE));
+
+	click_menu(&mut session, "[]");
+	drag(&mut session, (2, 3), (4, 5));
+	click_menu(&mut session, "^");
+	drag(&mut session, (3, 3), (3, 3));
+	drag(&mut session, (4, 5), (6, 6));
+	assert_eq!(
+		session.render_to_file(false),
+		"┏━━━┓\n┃   ┃\n┃   ┃\n┗━━━┛\n"
+	);
+
+	session.handle_event(key('z')).unwrap();
+	assert_eq!(session.render_to_file(false), "┏━┓\n┃ ┃\n┗━┛\n");
+	session.handle_event(key('Z')).unwrap();
+	assert_eq!(
+		session.render_to_file(false),
+		"┏━━━┓\n┃   ┃\n┃   ┃\n┗━━━┛\n"
+	);
+}
+
+#[test]
+fn saves_markdown_block_unchanged() {
+	let directory = std::env::temp_dir().join(format!("tpaint-block-{}", std::process::id()));
+	std::fs::create_dir_all(&directory).unwrap();
+	let file = directory.join("notes.md");
+	let text = concat!(
+		"# Notes\r\n",
+		"\r\n",
+		"  ```text\r\n",
+		"  ignored\r\n",
+		"  ```\r\n",
+		"\r\n",
+		"  ~~~~ diagram\r\n",
+		"\r\n",
+		"      +--+\r\n",
+		"    --|  |\r\n",
+		"      +--+\r\n",
+		"\r\n",
+		"  ~~~~\r\n",
+		"after\r\n",
+	);
+	std::fs::write(&file, text).unwrap();
+
+	for selector in &["diagram", "2"] {
+		let region = Region::Block(selector.parse::<BlockSelector>().unwrap());
+		let mut session = Session::open_region(40, 12, file.clone(), region).unwrap();
+		assert_eq!(session.render_to_file(false), "  +--+\n--|  |\n  +--+\n");
+		for key in &['s', 'e'] {
+			session
+				.handle_event(Event::Key(KeyEvent::new(
+					KeyCode::Char(*key),
+					KeyModifiers::NONE,
+				)))
+				.unwrap();
+		}
+		assert_eq!(std::fs::read(&file).unwrap(), text.as_bytes());
+
+		let document = directory.join(format!("notes.{}.tpaint", selector));
+		assert!(document.exists());
+		std::fs::remove_file(document).unwrap();
+	}
+
+	// Drawing into a block of ascii art keeps to ascii, and leaves the rest of the file alone
+	let region = Region::Block("diagram".parse::<BlockSelector>().unwrap());
+	let mut session = Session::open_region(40, 12, file.clone(), region).unwrap();
+	session
+		.replay("tool rectangle\ndown 8 0\ndrag 10 2\nup 10 2\nkey e")
+		.unwrap();
+	assert_eq!(
+		std::fs::read_to_string(&file).unwrap(),
+		text.replace(
+			"      +--+\r\n    --|  |\r\n      +--+\r\n",
+			"      +--+  +-+\r\n    --|  |  | |\r\n      +--+  +-+\r\n",
+		)
+	);
+	assert_eq!(std::fs::read_dir(&directory).unwrap().count(), 1);
+
+	std::fs::remove_dir_all(directory).unwrap();
+}
+
+#[test]
+fn pastes_into_text_being_typed() {
+	let mut session = Session::new(60, 12).unwrap();
+	session
+		.replay("tool text\ndown 2 1\nup 2 1\ntype ab\npaste cd\\nef\ntype g\nkey esc")
+		.unwrap();
+	assert_eq!(session.render_to_file(false), "abcd\nefg\n");
+
+	// The text and what was pasted into it are undone together
+	session.replay("key z").unwrap();
+	assert_eq!(session.render_to_file(false), "");
+}
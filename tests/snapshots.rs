@@ -0,0 +1,39 @@
+use std::{env, fs, path::Path};
+
+use tpaint::headless::Session;
+
+/// Replays `tests/scripts/<name>.script` and compares the exported drawing against the golden files
+/// in `tests/snapshots`. Set `UPDATE_SNAPSHOTS=1` to rewrite the golden files instead.
+fn check_snapshot(name: &str) {
+	let tests = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests");
+	let script =
+		fs::read_to_string(tests.join("scripts").join(format!("{}.script", name))).unwrap();
+
+	let mut session = Session::new(80, 24).unwrap();
+	session.replay(&script).unwrap();
+
+	for (mode, ascii_mode) in &[("unicode", false), ("ascii", true)] {
+		let path = tests
+			.join("snapshots")
+			.join(format!("{}.{}.txt", name, mode));
+		let output = session.render_to_file(*ascii_mode);
+		if env::var_os("UPDATE_SNAPSHOTS").is_some() {
+			fs::write(&path, &output).unwrap();
+		}
+		else {
+			let expected = fs::read_to_string(&path).unwrap();
+			assert_eq!(output, expected, "{} differs", path.display());
+		}
+	}
+}
+
+macro_rules! snapshot_tests {
+	($($name:ident),*) => {
+		$(
+			#[test]
+			fn $name() { check_snapshot(stringify!($name)); }
+		)*
+	};
+}
+
+snapshot_tests!(freehand, erase, rectangle, text, line, thick_line, block);